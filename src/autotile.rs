@@ -0,0 +1,84 @@
+use crate::*;
+use sprite_atlas::SpriteRegistry;
+
+pub const N: u8 = 1 << 0;
+pub const NE: u8 = 1 << 1;
+pub const E: u8 = 1 << 2;
+pub const SE: u8 = 1 << 3;
+pub const S: u8 = 1 << 4;
+pub const SW: u8 = 1 << 5;
+pub const W: u8 = 1 << 6;
+pub const NW: u8 = 1 << 7;
+
+/// Which of the 8 tiles around a tile match, one bit per direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Neighbours(pub u8);
+
+impl Neighbours {
+    /// Grid offsets in bit order, clockwise from the tile above
+    pub const OFFSETS: [(i32, i32); 8] = [
+        (0, -1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+    ];
+
+    pub fn of(pos: GridPos, matches: impl Fn(GridPos) -> bool) -> Self {
+        let bits = Self::OFFSETS
+            .iter()
+            .enumerate()
+            .filter(|(_, (i, j))| matches(pos.offset(*i, *j)))
+            .fold(0, |bits, (idx, _)| bits | 1 << idx);
+        Self(bits)
+    }
+
+    pub fn has(self, bits: u8) -> bool {
+        self.0 & bits == bits
+    }
+
+    /// Corners where both sides match but the diagonal doesn't,
+    /// 1 top left, 2 top right, 4 bottom right, 8 bottom left
+    pub fn inner_corners(self) -> usize {
+        [(N | W, NW), (N | E, NE), (S | E, SE), (S | W, SW)]
+            .iter()
+            .enumerate()
+            .filter(|(_, (sides, diagonal))| self.has(*sides) && !self.has(*diagonal))
+            .fold(0, |mask, (idx, _)| mask | 1 << idx)
+    }
+}
+
+/// Sprite of a road tile given which of its neighbours are roads, only sides connect
+pub fn road_sprite(roads: Neighbours, sprites: &SpriteRegistry) -> usize {
+    let mask = [N, E, S, W]
+        .iter()
+        .enumerate()
+        .filter(|(_, side)| roads.has(**side))
+        .fold(0, |mask, (idx, _)| mask | 1 << idx);
+    sprites.get("road").frame(mask)
+}
+
+/// Sprite of a land tile given which of its neighbours are land
+pub fn shore_sprite(land: Neighbours, sprites: &SpriteRegistry) -> usize {
+    let sides = (land.has(N), land.has(E), land.has(S), land.has(W));
+    let tile = match sides {
+        (false, false, false, false) => "shore.island",
+        (false, false, true, false) => return sprites.get("shore.tip").frame(0),
+        (false, false, false, true) => return sprites.get("shore.tip").frame(1),
+        (true, false, false, false) => return sprites.get("shore.tip").frame(2),
+        (false, true, false, false) => return sprites.get("shore.tip").frame(3),
+        (true, true, false, false) => "shore.bottom_left",
+        (true, false, false, true) => "shore.bottom_right",
+        (false, true, true, false) => "shore.top_left",
+        (false, false, true, true) => "shore.top_right",
+        _ => match land.inner_corners() {
+            0 => "ground",
+            corners => return sprites.get("shore.inner").frame(corners - 1),
+        },
+    };
+
+    sprites.get(tile).cell
+}
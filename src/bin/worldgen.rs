@@ -0,0 +1,74 @@
+use bevy::math::UVec2;
+use std::{env, fs, path::PathBuf, process::ExitCode};
+
+use game::{
+    biome::BiomeRegistry,
+    seed::seed_from_text,
+    world_map::render_world_map,
+    worldgen::{WorldBounds, WorldPreset},
+    WorldPos, BIOME_CONFIG_PATH, CHUNK_H, CHUNK_W, GRID_COLS, GRID_ROWS,
+};
+
+// cargo run --bin worldgen -- --seed 42 --size 6x4 --preset continent --out map.png
+// cargo run --bin worldgen -- --seed 42 --bounded --biomes my.biomes.ron
+fn main() -> ExitCode {
+    let arg = |name: &str| env::args().skip_while(|arg| arg != name).nth(1);
+
+    let seed = arg("--seed").map_or_else(rand::random, |text| seed_from_text(&text));
+    let preset: WorldPreset = match arg("--preset").map(|name| name.parse()) {
+        Some(Ok(preset)) => preset,
+        Some(Err(e)) => return fail(e),
+        None => WorldPreset::default(),
+    };
+    let mut settings = preset.settings();
+    if env::args().any(|arg| arg == "--bounded") {
+        settings.bounds = WorldBounds::Grid;
+    }
+    // Chunks across and down, centred on the spawn. Bounded worlds show all of it by default.
+    let size = match arg("--size") {
+        Some(size) => match size.split_once('x').map(|(w, h)| (w.parse(), h.parse())) {
+            Some((Ok(w), Ok(h))) if w > 0 && h > 0 => UVec2::new(w, h),
+            _ => return fail(format!("bad --size `{size}`, expected WxH like 6x4")),
+        },
+        None if settings.bounds == WorldBounds::Grid => UVec2::new(
+            GRID_COLS.div_ceil(CHUNK_W) as u32 + 2,
+            GRID_ROWS.div_ceil(CHUNK_H) as u32 + 2,
+        ),
+        None => UVec2::new(4, 4),
+    };
+    let out = arg("--out").unwrap_or_else(|| format!("worldgen-{seed}.png"));
+
+    // The game's own biomes wherever the binary is run from
+    let config = arg("--biomes").map_or_else(
+        || {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("assets")
+                .join(BIOME_CONFIG_PATH)
+        },
+        PathBuf::from,
+    );
+    let biomes = match fs::read_to_string(&config).map(|ron| BiomeRegistry::from_ron(&ron)) {
+        Ok(Ok(biomes)) => biomes,
+        Ok(Err(e)) => return fail(format!("{}: {e}", config.display())),
+        Err(e) => return fail(format!("{}: {e}", config.display())),
+    };
+
+    let spawn = WorldPos::new(0.0, 0.0).to_chunk();
+    let from = spawn.offset(-(size.x as i32) / 2, -(size.y as i32) / 2);
+    let image = render_world_map(seed, from, size, &settings, &biomes);
+    let saved = image
+        .try_into_dynamic()
+        .map_err(|e| e.to_string())
+        .and_then(|image| image.save(&out).map_err(|e| e.to_string()));
+    if let Err(e) = saved {
+        return fail(format!("{out}: {e}"));
+    }
+
+    println!("seed {seed} written to {out}");
+    ExitCode::SUCCESS
+}
+
+fn fail(message: String) -> ExitCode {
+    eprintln!("worldgen: {message}");
+    ExitCode::FAILURE
+}
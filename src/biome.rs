@@ -0,0 +1,175 @@
+use crate::*;
+use bevy::prelude::*;
+use ron_asset::RonAssetLoader;
+use serde::Deserialize;
+use std::sync::Arc;
+use terrain::{GenerationSeed, ResetTerrainEvent};
+use world::Feature;
+
+pub struct BiomePlugin;
+
+/// Biome config, regenerates the world when the file changes
+#[derive(Resource)]
+pub struct Biomes {
+    pub handle: Handle<BiomeRegistry>,
+    /// Available once the config is loaded
+    pub registry: Option<Arc<BiomeRegistry>>,
+}
+
+/// Noise fields a tile is generated from, all roughly in -1.0..1.0
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Climate {
+    /// Land starts at 0.0
+    pub elevation: f64,
+    pub temperature: f64,
+    pub moisture: f64,
+    /// Fine grained noise features clump by, like groves of trees
+    pub patches: f64,
+    /// Noise mineral veins follow
+    pub minerals: f64,
+    /// Noise of how good the soil is, apart from the biome
+    pub soil: f64,
+}
+
+/// Biomes in priority order, loaded from the biome config
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct BiomeRegistry {
+    pub biomes: Vec<Biome>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Biome {
+    pub name: String,
+    #[serde(default = "any")]
+    pub elevation: (f64, f64),
+    #[serde(default = "any")]
+    pub temperature: (f64, f64),
+    #[serde(default = "any")]
+    pub moisture: (f64, f64),
+    /// How well crops grow here in 0.0..1.0, before soil noise and water
+    #[serde(default = "half")]
+    pub fertility: f64,
+    #[serde(default)]
+    pub features: Vec<FeatureSpawn>,
+}
+
+/// One row of a biome's spawn table
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct FeatureSpawn {
+    pub feature: Feature,
+    /// Probability of spawning on a tile
+    pub chance: f64,
+    #[serde(default = "any")]
+    pub patches: (f64, f64),
+    #[serde(default = "any")]
+    pub minerals: (f64, f64),
+}
+
+fn any() -> (f64, f64) {
+    (f64::NEG_INFINITY, f64::INFINITY)
+}
+
+fn half() -> f64 {
+    0.5
+}
+
+fn in_range(value: f64, (min, max): (f64, f64)) -> bool {
+    (min..max).contains(&value)
+}
+
+impl Plugin for BiomePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BiomeRegistry>()
+            .register_asset_loader(RonAssetLoader::<BiomeRegistry>::new(&["biomes.ron"]))
+            .add_systems(PreStartup, setup)
+            .add_systems(Update, load_biomes);
+    }
+}
+
+impl BiomeRegistry {
+    pub fn from_ron(config: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(config)
+    }
+
+    /// Index of the first biome matching the climate, the last biome is the fallback
+    pub fn biome_at(&self, climate: &Climate) -> usize {
+        self.biomes
+            .iter()
+            .position(|b| b.matches(climate))
+            .unwrap_or(self.biomes.len() - 1)
+    }
+}
+
+impl Biome {
+    pub fn matches(&self, climate: &Climate) -> bool {
+        in_range(climate.elevation, self.elevation)
+            && in_range(climate.temperature, self.temperature)
+            && in_range(climate.moisture, self.moisture)
+    }
+
+    /// Walks the spawn table with a roll in 0.0..1.0, rows share the roll
+    /// so their chances add up instead of shadowing each other
+    pub fn pick_feature(&self, climate: &Climate, roll: f64) -> Option<Feature> {
+        let mut total = 0.0;
+        for spawn in self.features.iter() {
+            if !in_range(climate.patches, spawn.patches)
+                || !in_range(climate.minerals, spawn.minerals)
+            {
+                continue;
+            }
+
+            total += spawn.chance;
+            if roll < total {
+                return Some(spawn.feature);
+            }
+        }
+        None
+    }
+
+    /// Odds of the spawn table picking a feature `matching` at the climate
+    pub fn chance_of(&self, climate: &Climate, matching: impl Fn(Feature) -> bool) -> f64 {
+        self.features
+            .iter()
+            .filter(|spawn| {
+                in_range(climate.patches, spawn.patches)
+                    && in_range(climate.minerals, spawn.minerals)
+                    && matching(spawn.feature)
+            })
+            .map(|spawn| spawn.chance)
+            .sum()
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Biomes {
+        handle: asset_server.load(BIOME_CONFIG_PATH),
+        registry: None,
+    });
+}
+
+fn load_biomes(
+    mut events: EventReader<AssetEvent<BiomeRegistry>>,
+    mut reset_writer: EventWriter<ResetTerrainEvent>,
+    registries: Res<Assets<BiomeRegistry>>,
+    seed: Res<GenerationSeed>,
+    mut biomes: ResMut<Biomes>,
+) {
+    for ev in events.read() {
+        let modified = ev.is_modified(&biomes.handle);
+        if !ev.is_loaded_with_dependencies(&biomes.handle) && !modified {
+            continue;
+        }
+        let Some(registry) = registries.get(&biomes.handle) else {
+            continue;
+        };
+        if registry.biomes.is_empty() {
+            error!("{BIOME_CONFIG_PATH} has no biomes");
+            continue;
+        }
+
+        biomes.registry = Some(Arc::new(registry.clone()));
+        if modified {
+            reset_writer.write(ResetTerrainEvent(Some(seed.0)));
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::*;
 use bevy::{app::*, prelude::*};
 use bevy_pancam::{PanCam, PanCamPlugin};
+use seed::SeedEntry;
+use worldgen::WorldGenSettings;
 
 fn camera_setup(mut commands: Commands) {
     commands.spawn((
@@ -20,11 +23,42 @@ fn camera_setup(mut commands: Commands) {
         // },
     ));
 }
+
+/// Keeps the view inside a bounded world
+fn bound_camera(settings: Res<WorldGenSettings>, mut pancam_q: Query<&mut PanCam>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let rect = settings.bounds.world_rect().unwrap_or(Rect {
+        min: Vec2::NEG_INFINITY,
+        max: Vec2::INFINITY,
+    });
+    for mut pancam in pancam_q.iter_mut() {
+        pancam.min_x = rect.min.x;
+        pancam.max_x = rect.max.x;
+        pancam.min_y = rect.min.y;
+        pancam.max_y = rect.max.y;
+    }
+}
+
+/// Leaves the mouse wheel and dragging alone while the new world screen is open
+fn hold_camera_for_seed_entry(entry: Res<SeedEntry>, mut pancam_q: Query<&mut PanCam>) {
+    if !entry.is_changed() {
+        return;
+    }
+
+    for mut pancam in pancam_q.iter_mut() {
+        pancam.enabled = entry.0.is_none();
+    }
+}
+
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(PanCamPlugin::default())
-            .add_systems(Startup, camera_setup);
+        app.add_plugins(PanCamPlugin)
+            .add_systems(Startup, camera_setup)
+            .add_systems(Update, (bound_camera, hold_camera_for_seed_entry));
     }
 }
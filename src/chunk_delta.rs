@@ -0,0 +1,47 @@
+use crate::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use world::{ChunkData, TileData};
+
+/// Player made changes to every chunk, applied on top of the procedural tiles
+#[derive(Resource, Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ChunkDeltas(pub HashMap<ChunkPos, ChunkDelta>);
+
+/// Tiles of one chunk that no longer match what generation produces
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct ChunkDelta(pub HashMap<GridPos, TileData>);
+
+/// Asks the terrain to record a tile change and redraw its chunk
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TileEditEvent {
+    pub pos: GridPos,
+    pub tile: TileData,
+}
+
+impl ChunkDeltas {
+    pub fn record(&mut self, edit: &TileEditEvent) {
+        self.0
+            .entry(edit.pos.to_chunk())
+            .or_default()
+            .0
+            .insert(edit.pos, edit.tile);
+    }
+
+    /// Deltas of the chunk and its neighbours, which may reach into its apron
+    pub fn around(&self, chunk: ChunkPos) -> Vec<ChunkDelta> {
+        (-1..=1)
+            .flat_map(|i| (-1..=1).map(move |j| chunk.offset(i, j)))
+            .filter_map(|c| self.0.get(&c).cloned())
+            .collect()
+    }
+}
+
+impl ChunkDelta {
+    /// Tiles outside the chunk and its apron are skipped
+    pub fn apply(&self, data: &mut ChunkData) {
+        for (pos, tile) in self.0.iter() {
+            data.set(*pos, *tile);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::*;
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use std::collections::BTreeMap;
+use terrain::TileSprite;
+
+/// Every tile of a chunk sharing the same z index, batched into one mesh
+pub struct ChunkLayerMesh {
+    pub z_index: i32,
+    pub mesh: Mesh,
+}
+
+/// Normalised uv rect of every cell in the atlas
+pub fn atlas_uvs(layout: &TextureAtlasLayout, image_size: UVec2) -> Vec<Rect> {
+    let size = image_size.as_vec2();
+    layout
+        .textures
+        .iter()
+        .map(|r| Rect::from_corners(r.min.as_vec2() / size, r.max.as_vec2() / size))
+        .collect()
+}
+
+/// World position of the chunk mesh origin, the centre of its first tile
+pub fn chunk_origin(chunk: ChunkPos) -> Vec2 {
+    chunk.origin().to_world().into()
+}
+
+pub fn build_chunk_meshes<'a>(
+    chunk: ChunkPos,
+    tiles: impl IntoIterator<Item = &'a TileSprite>,
+    uvs: &[Rect],
+) -> Vec<ChunkLayerMesh> {
+    let mut layers: BTreeMap<i32, Vec<&TileSprite>> = BTreeMap::new();
+    for t in tiles {
+        layers.entry(t.z_index).or_default().push(t);
+    }
+
+    layers
+        .into_iter()
+        .map(|(z_index, tiles)| ChunkLayerMesh {
+            z_index,
+            mesh: build_layer_mesh(chunk, &tiles, uvs),
+        })
+        .collect()
+}
+
+fn build_layer_mesh(chunk: ChunkPos, tiles: &[&TileSprite], uvs: &[Rect]) -> Mesh {
+    let start = chunk.origin();
+    let (w, h) = (
+        (TILE_W * SPRITE_SCALE_FACTOR) as f32,
+        (TILE_H * SPRITE_SCALE_FACTOR) as f32,
+    );
+
+    let mut positions = Vec::with_capacity(tiles.len() * 4);
+    let mut tex_coords = Vec::with_capacity(tiles.len() * 4);
+    let mut indices = Vec::with_capacity(tiles.len() * 6);
+    for t in tiles {
+        // Grid rows grow downwards, world y grows upwards
+        let x = (t.pos.x - start.x) as f32 * w;
+        let y = -(t.pos.y - start.y) as f32 * h;
+        let uv = uvs[t.sprite];
+
+        let i = positions.len() as u32;
+        positions.extend([
+            [x - w / 2.0, y + h / 2.0, 0.0],
+            [x + w / 2.0, y + h / 2.0, 0.0],
+            [x + w / 2.0, y - h / 2.0, 0.0],
+            [x - w / 2.0, y - h / 2.0, 0.0],
+        ]);
+        tex_coords.extend([
+            [uv.min.x, uv.min.y],
+            [uv.max.x, uv.min.y],
+            [uv.max.x, uv.max.y],
+            [uv.min.x, uv.max.y],
+        ]);
+        indices.extend([i, i + 2, i + 1, i, i + 3, i + 2]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, tex_coords)
+    .with_inserted_indices(Indices::U32(indices))
+}
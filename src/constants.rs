@@ -2,10 +2,10 @@
 pub const TILE_W: usize = 6;
 pub const TILE_H: usize = 8;
 pub const SPRITE_SHEET_W: usize = 8;
-pub const SPRITE_SHEET_H: usize = 9;
+pub const SPRITE_SHEET_H: usize = 14;
 pub const SPRITE_SCALE_FACTOR: usize = 5;
-pub const PLAYER_SPRITE_INDEX: usize = 56;
 pub const SPRITE_SHEET_PATH: &str = "sprite-sheet.png";
+pub const SPRITE_MANIFEST_PATH: &str = "sprite-sheet.atlas.ron";
 pub const SPRITE_PADDING: u32 = 2;
 pub const SPRITE_SHEET_OFFSET: u32 = 2;
 
@@ -18,6 +18,14 @@ pub const WINDOW_W: usize = 1920;
 pub const WINDOW_H: usize = 1080;
 pub const BG_COLOR: (u8, u8, u8) = (181, 212, 220);
 
+// World generation
+/// Environment variable the world seed is read from when not given with `--seed`
+pub const SEED_ENV_VAR: &str = "WORLD_SEED";
+pub const BIOME_CONFIG_PATH: &str = "default.biomes.ron";
+
+// Save
+pub const SAVE_PATH: &str = "saves/world.ron";
+
 // Chunk
 pub const CHUNK_W: usize = 200;
 pub const CHUNK_H: usize = 100;
@@ -0,0 +1,90 @@
+use crate::*;
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+/// Water further away than this many tiles doesn't help crops
+pub const FERTILITY_WATER_REACH: i32 = 6;
+
+/// How well crops grow on the tiles of every loaded chunk, kept next to `WorldTiles`
+#[derive(Resource, Default)]
+pub struct Fertility(pub HashMap<ChunkPos, FertilityLayer>);
+
+/// Fertility of the tiles of one chunk, from 0.0 barren to 1.0 lush, water is 0.0
+#[derive(Debug, Clone, PartialEq)]
+pub struct FertilityLayer {
+    pub pos: ChunkPos,
+    values: Vec<f32>,
+}
+
+impl Fertility {
+    /// `None` while the chunk holding the tile isn't loaded
+    pub fn at(&self, pos: GridPos) -> Option<f32> {
+        self.0.get(&pos.to_chunk())?.get(pos)
+    }
+}
+
+impl FertilityLayer {
+    pub fn get(&self, pos: GridPos) -> Option<f32> {
+        if pos.to_chunk() != self.pos {
+            return None;
+        }
+        let local = pos.to_local();
+        Some(self.values[local.y as usize * CHUNK_W + local.x as usize])
+    }
+}
+
+/// Fertility of a chunk from the `soil` of its land tiles and how close they are
+/// to water. `is_land` is asked up to `FERTILITY_WATER_REACH` tiles around the
+/// chunk, so neighbouring chunks agree on their borders.
+pub fn fertility_layer(
+    chunk: ChunkPos,
+    is_land: impl Fn(GridPos) -> bool,
+    soil: impl Fn(GridPos) -> f64,
+) -> FertilityLayer {
+    let reach = FERTILITY_WATER_REACH;
+    let start = chunk.origin().offset(-reach, -reach);
+    let (w, h) = (CHUNK_W as i32 + 2 * reach, CHUNK_H as i32 + 2 * reach);
+    let inside =
+        |p: GridPos| (start.x..start.x + w).contains(&p.x) && (start.y..start.y + h).contains(&p.y);
+    let index = |p: GridPos| ((p.y - start.y) * w + p.x - start.x) as usize;
+
+    // Steps to the closest water, spreading out from every water tile at once
+    let mut distance = vec![i32::MAX; (w * h) as usize];
+    let mut queue = VecDeque::new();
+    for y in start.y..start.y + h {
+        for x in start.x..start.x + w {
+            let pos = GridPos::new(x, y);
+            if !is_land(pos) {
+                distance[index(pos)] = 0;
+                queue.push_back(pos);
+            }
+        }
+    }
+    while let Some(pos) = queue.pop_front() {
+        let steps = distance[index(pos)] + 1;
+        if steps > reach {
+            continue;
+        }
+        for (i, j) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let nei = pos.offset(i, j);
+            if inside(nei) && distance[index(nei)] > steps {
+                distance[index(nei)] = steps;
+                queue.push_back(nei);
+            }
+        }
+    }
+
+    let values = (0..CHUNK_H as u32)
+        .flat_map(|y| (0..CHUNK_W as u32).map(move |x| LocalTilePos { x, y }))
+        .map(|local| chunk.tile(local))
+        .map(|pos| match distance[index(pos)] {
+            0 => 0.0,
+            steps => {
+                let water = 1.0 - steps.min(reach + 1) as f64 / (reach + 1) as f64;
+                (soil(pos) * 0.75 + water * 0.25).clamp(0.0, 1.0) as f32
+            }
+        })
+        .collect();
+
+    FertilityLayer { pos: chunk, values }
+}
@@ -0,0 +1,245 @@
+use crate::*;
+use autotile::Neighbours;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+use world::TerrainKind;
+use worldgen::Elevation;
+
+/// Where rivers spring and how far basins fill into lakes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RiverSettings {
+    /// The world is split into squares this many tiles wide, each with at most one source
+    pub source_spacing: i32,
+    pub source_chance: f64,
+    /// Rivers only spring from high ground
+    pub min_source_elevation: f64,
+    /// Rivers that haven't reached the sea or a basin after this many tiles dry up
+    pub max_length: usize,
+    /// Basins fill up to this much above their lowest tile
+    pub lake_depth: f64,
+    pub max_lake_tiles: usize,
+}
+
+/// Rivers of one world traced so far by source square, so the chunks a river flows
+/// through don't each trace it again. Shared by every chunk being generated.
+#[derive(Default)]
+pub struct RiverCache(Mutex<HashMap<(i32, i32), Arc<TracedRiver>>>);
+
+/// Tiles of a river and its lake in the order they were traced, within `min..max`
+struct TracedRiver {
+    tiles: Vec<(GridPos, TerrainKind)>,
+    min: GridPos,
+    max: GridPos,
+}
+
+/// Elevation of every tile looked at while tracing, rivers share most of them
+struct Heights<'a> {
+    elevation: &'a Elevation,
+    cache: HashMap<GridPos, f64>,
+}
+
+impl Default for RiverSettings {
+    fn default() -> Self {
+        Self {
+            source_spacing: 48,
+            source_chance: 0.5,
+            min_source_elevation: 0.2,
+            max_length: 300,
+            lake_depth: 0.02,
+            max_lake_tiles: 120,
+        }
+    }
+}
+
+impl Heights<'_> {
+    fn get(&mut self, pos: GridPos) -> f64 {
+        *self
+            .cache
+            .entry(pos)
+            .or_insert_with(|| self.elevation.get([pos.x as f64, pos.y as f64]))
+    }
+}
+
+/// Rivers and lakes covering the tiles in `min..max`. Every source close enough
+/// to reach the area is traced in full, so neighbouring areas always agree.
+pub fn trace_fresh_water(
+    gen_seed: u32,
+    elevation: &Elevation,
+    settings: &RiverSettings,
+    min: GridPos,
+    max: GridPos,
+) -> HashMap<GridPos, TerrainKind> {
+    RiverCache::default().fresh_water(gen_seed, elevation, settings, min, max)
+}
+
+impl RiverCache {
+    /// Same as `trace_fresh_water`, only tracing the rivers no earlier area needed.
+    /// The seed and settings must stay those of the first call.
+    pub fn fresh_water(
+        &self,
+        gen_seed: u32,
+        elevation: &Elevation,
+        settings: &RiverSettings,
+        min: GridPos,
+        max: GridPos,
+    ) -> HashMap<GridPos, TerrainKind> {
+        let mut heights = Heights {
+            elevation,
+            cache: HashMap::new(),
+        };
+        let mut water = HashMap::new();
+
+        let reach = (settings.max_length + settings.max_lake_tiles) as i32;
+        let spacing = settings.source_spacing;
+        for cx in (min.x - reach).div_euclid(spacing)..=(max.x + reach).div_euclid(spacing) {
+            for cy in (min.y - reach).div_euclid(spacing)..=(max.y + reach).div_euclid(spacing) {
+                let river = self.river(gen_seed, &mut heights, settings, (cx, cy));
+                if river.min.x >= max.x
+                    || river.max.x <= min.x
+                    || river.min.y >= max.y
+                    || river.max.y <= min.y
+                {
+                    continue;
+                }
+
+                for (pos, kind) in river.tiles.iter() {
+                    if (min.x..max.x).contains(&pos.x) && (min.y..max.y).contains(&pos.y) {
+                        // Lakes win where they flood a river, whichever was traced first
+                        let tile = water.entry(*pos).or_insert(*kind);
+                        if *kind == TerrainKind::Lake {
+                            *tile = *kind;
+                        }
+                    }
+                }
+            }
+        }
+
+        water
+    }
+
+    fn river(
+        &self,
+        gen_seed: u32,
+        heights: &mut Heights,
+        settings: &RiverSettings,
+        cell: (i32, i32),
+    ) -> Arc<TracedRiver> {
+        if let Some(river) = self.0.lock().unwrap().get(&cell) {
+            return river.clone();
+        }
+
+        // Traced without holding the lock, at worst two chunks trace the same river
+        let mut tiles = Vec::new();
+        if let Some(source) = river_source(gen_seed, heights, settings, cell) {
+            trace_river(source, heights, settings, &mut |pos, kind| {
+                tiles.push((pos, kind))
+            });
+        }
+        let first = tiles.first().map_or(GridPos::new(0, 0), |(p, _)| *p);
+        let (min, max) = tiles.iter().fold((first, first), |(min, max), (p, _)| {
+            (
+                GridPos::new(min.x.min(p.x), min.y.min(p.y)),
+                GridPos::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        });
+        let river = Arc::new(TracedRiver {
+            tiles,
+            min,
+            max: max.offset(1, 1),
+        });
+
+        self.0.lock().unwrap().entry(cell).or_insert(river).clone()
+    }
+}
+
+fn river_source(
+    gen_seed: u32,
+    heights: &mut Heights,
+    settings: &RiverSettings,
+    (cx, cy): (i32, i32),
+) -> Option<GridPos> {
+    let mut rng_seed = [0; 32];
+    rng_seed[0..4].copy_from_slice(&gen_seed.to_le_bytes());
+    rng_seed[4..8].copy_from_slice(&cx.to_le_bytes());
+    rng_seed[8..12].copy_from_slice(&cy.to_le_bytes());
+    // Keeps these rolls apart from the chunk rng of the same coordinates
+    rng_seed[12] = 1;
+    let mut rng = ChaCha8Rng::from_seed(rng_seed);
+
+    if rng.random_range(0.0..1.0) >= settings.source_chance {
+        return None;
+    }
+    let spacing = settings.source_spacing;
+    let source = GridPos::new(
+        cx * spacing + rng.random_range(0..spacing),
+        cy * spacing + rng.random_range(0..spacing),
+    );
+    (heights.get(source) >= settings.min_source_elevation).then_some(source)
+}
+
+/// Follows the steepest way down until the sea, or fills the basin it gets stuck in
+fn trace_river(
+    source: GridPos,
+    heights: &mut Heights,
+    settings: &RiverSettings,
+    add: &mut impl FnMut(GridPos, TerrainKind),
+) {
+    let mut pos = source;
+    for _ in 0..settings.max_length {
+        let height = heights.get(pos);
+        if height <= 0.0 {
+            return;
+        }
+        add(pos, TerrainKind::River);
+
+        let (next, next_height) = Neighbours::OFFSETS
+            .iter()
+            .map(|(i, j)| pos.offset(*i, *j))
+            .map(|p| (p, heights.get(p)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        if next_height >= height {
+            fill_lake(pos, heights, settings, add);
+            return;
+        }
+
+        // Diagonal steps would leave the river joined by a corner only
+        if next.x != pos.x && next.y != pos.y && heights.get(GridPos::new(next.x, pos.y)) > 0.0 {
+            add(GridPos::new(next.x, pos.y), TerrainKind::River);
+        }
+        pos = next;
+    }
+}
+
+fn fill_lake(
+    basin: GridPos,
+    heights: &mut Heights,
+    settings: &RiverSettings,
+    add: &mut impl FnMut(GridPos, TerrainKind),
+) {
+    let surface = heights.get(basin) + settings.lake_depth;
+    let mut seen = HashSet::from([basin]);
+    let mut queue = VecDeque::from([basin]);
+    let mut filled = 0;
+    while let Some(pos) = queue.pop_front() {
+        if filled == settings.max_lake_tiles {
+            return;
+        }
+        add(pos, TerrainKind::Lake);
+        filled += 1;
+
+        for (i, j) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let nei = pos.offset(i, j);
+            let height = heights.get(nei);
+            if height > 0.0 && height < surface && seen.insert(nei) {
+                queue.push_back(nei);
+            }
+        }
+    }
+}
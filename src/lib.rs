@@ -1,9 +1,24 @@
+pub mod autotile;
+pub mod biome;
 pub mod camera;
+pub mod chunk_delta;
+pub mod chunk_mesh;
 pub mod constants;
+pub mod fertility;
+pub mod hydrology;
 pub mod player;
+pub mod roads;
+pub mod ron_asset;
+pub mod save;
+pub mod seed;
+pub mod settlement;
 pub mod shared;
 pub mod show_fps;
+pub mod sprite_atlas;
 pub mod terrain;
+pub mod world;
+pub mod world_map;
+pub mod worldgen;
 
 pub use constants::*;
 pub use shared::*;
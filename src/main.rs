@@ -1,17 +1,52 @@
-use std::env;
 use bevy::prelude::*;
+use std::{env, path::PathBuf, process};
 
 use game::{
-    camera::CameraPlugin, player::*, show_fps::ShowFPSPlugin, terrain::*, BG_COLOR, WINDOW_H,
-    WINDOW_W,
+    biome::BiomePlugin,
+    camera::CameraPlugin,
+    player::*,
+    save::*,
+    seed::*,
+    show_fps::ShowFPSPlugin,
+    sprite_atlas::SpriteAtlasPlugin,
+    terrain::*,
+    worldgen::{WorldBounds, WorldPreset},
+    BG_COLOR, SEED_ENV_VAR, WINDOW_H, WINDOW_W,
 };
 
-
 fn main() {
     env::set_var("RUST_LOG", "info");
     env::set_var("RUST_BACKTRACE", "1");
     env::set_var("BEVY_ASSET_ROOT", "%USERPROFILE%\\Desktop");
 
+    // cargo run -- --load saves/world.ron
+    let load = env::args()
+        .skip_while(|arg| arg != "--load")
+        .nth(1)
+        .map(PathBuf::from);
+    // cargo run -- --preset archipelago
+    let preset: WorldPreset = env::args()
+        .skip_while(|arg| arg != "--preset")
+        .nth(1)
+        .map(|name| {
+            name.parse().unwrap_or_else(|e| {
+                eprintln!("{e}");
+                process::exit(1);
+            })
+        })
+        .unwrap_or_default();
+    // cargo run -- --seed 1234, or any text like --seed "my world"
+    let seed = env::args()
+        .skip_while(|arg| arg != "--seed")
+        .nth(1)
+        .or_else(|| env::var(SEED_ENV_VAR).ok())
+        .map(|text| seed_from_text(&text));
+    // cargo run -- --bounded, an island of GRID_COLS x GRID_ROWS tiles
+    let mut settings = preset.settings();
+    if env::args().any(|arg| arg == "--bounded") {
+        settings.bounds = WorldBounds::Grid;
+    }
+
     App::new()
         .add_plugins(
             DefaultPlugins
@@ -31,8 +66,21 @@ fn main() {
         .insert_resource(ClearColor(Color::srgba_u8(
             BG_COLOR.0, BG_COLOR.1, BG_COLOR.2, 0,
         )))
-        .add_plugins((CameraPlugin, ShowFPSPlugin, TerrainPlugin, PlayerPlugin))
-        .add_systems(Update, (handle_settings_input, close_on_esc))
+        .insert_resource(settings)
+        .add_plugins((
+            CameraPlugin,
+            ShowFPSPlugin,
+            SpriteAtlasPlugin,
+            BiomePlugin,
+            TerrainPlugin { seed },
+            PlayerPlugin,
+            SavePlugin { load },
+            SeedPlugin,
+        ))
+        .add_systems(
+            Update,
+            (handle_settings_input, close_on_esc).run_if(not(typing_seed)),
+        )
         .run();
 }
 
@@ -55,10 +103,18 @@ pub fn close_on_esc(
 fn handle_settings_input(
     keys: Res<ButtonInput<KeyCode>>,
     mut writer: EventWriter<ResetTerrainEvent>,
+    mut save_writer: EventWriter<SaveGameEvent>,
+    mut load_writer: EventWriter<LoadGameEvent>,
 ) {
+    if keys.just_pressed(KeyCode::F5) {
+        save_writer.write(SaveGameEvent);
+    }
+    if keys.just_pressed(KeyCode::F9) {
+        load_writer.write(LoadGameEvent);
+    }
     if !keys.just_pressed(KeyCode::KeyR) {
         return;
     }
 
-    writer.write(ResetTerrainEvent);
+    writer.write(ResetTerrainEvent(None));
 }
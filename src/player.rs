@@ -1,39 +1,52 @@
 use crate::*;
 use bevy::{math::*, prelude::*};
-use std::time::Instant;
+use seed::typing_seed;
+use sprite_atlas::SpriteAtlas;
+use world::WorldTiles;
+use worldgen::WorldGenSettings;
 
 pub struct PlayerPlugin;
 
 #[derive(Component)]
-struct Player;
+pub struct Player;
 #[derive(Resource)]
 struct PlayerSpriteIndex(usize);
 #[derive(Component, Deref, DerefMut)]
 struct AnimationTimer(Timer);
+/// Game time left of the current jump, paused along with the game
+#[derive(Component, Deref, DerefMut)]
+pub struct JumpTimer(pub Timer);
 #[derive(Resource, Default)]
 struct PlayerDirection(f32);
-#[derive(Resource)]
-struct DefaultAtlasHandle(pub Option<Handle<TextureAtlasLayout>>);
 #[derive(Resource, Default)]
-pub struct CurrentPlayerChunkPosition(pub (i32, i32));
-#[derive(Event)]
-pub struct PlayerChunkUpdateEvent(pub (i32, i32));
-#[derive(Resource)]
-struct DefaultSpriteSheet(pub Option<Handle<Image>>);
-
-pub const PLAYER_SPEED: f32 = 2.0;
-pub const PLAYER_FISH_SPEED: f32 = 1.5;
+pub struct CurrentPlayerChunkPosition(pub ChunkPos);
+/// World units per second the player moves, set from input every frame
+#[derive(Component, Default, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+/// Position of the player as of the last fixed step, gameplay reads this while
+/// `Transform` is drawn between it and the previous one
+#[derive(Component, Default, Deref, DerefMut)]
+pub struct PhysicalTranslation(pub Vec2);
+#[derive(Component, Default, Deref, DerefMut)]
+pub struct PreviousPhysicalTranslation(pub Vec2);
+
+/// World units per second
+pub const PLAYER_SPEED: f32 = 120.0;
+pub const PLAYER_FISH_SPEED: f32 = 90.0;
+pub const PLAYER_SPRINT_SCALE: f32 = 5.0;
 pub const PLAYER_ANIMATION_INTERVAL: f32 = 0.3;
 pub const WALK_TRAIL_TIMER: f32 = 1.2;
 pub const TRAIL_LIFE_SPAN: f32 = 5.0;
 pub const PLAYER_JUMP_TIME: f32 = 0.3;
+/// How quickly the camera closes in on the player, about 5% of the way each frame at 60 fps
+pub const CAMERA_FOLLOW_RATE: f32 = 3.0;
 
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
-enum PlayerState {
+pub enum PlayerState {
     #[default]
     Idle,
     Walk,
-    Jump(Instant),
+    Jump,
     Swim,
 }
 
@@ -46,15 +59,8 @@ impl PlayerState {
         *self == PlayerState::Walk
     }
 
-    fn swimming(&self) -> bool {
-        *self == PlayerState::Swim
-    }
-
     fn jumping(&self) -> bool {
-        match self {
-            PlayerState::Jump(_) => true,
-            _ => false,
-        }
+        *self == PlayerState::Jump
     }
 }
 
@@ -64,48 +70,43 @@ impl Plugin for PlayerPlugin {
             .insert_resource(PlayerSpriteIndex(0))
             .insert_resource(PlayerDirection(0.0))
             .insert_resource(CurrentPlayerChunkPosition::default())
-            .insert_resource(DefaultAtlasHandle(None))
-            .insert_resource(DefaultSpriteSheet(None))
-            .add_event::<PlayerChunkUpdateEvent>()
             .add_systems(Startup, setup)
+            .add_systems(OnEnter(PlayerState::Jump), start_jump)
+            .add_systems(OnExit(PlayerState::Jump), land_jump)
+            .add_systems(OnEnter(PlayerState::Idle), stand_upright)
+            .add_systems(OnEnter(PlayerState::Walk), start_walk)
+            .add_systems(OnExit(PlayerState::Walk), stop_walk)
+            .add_systems(OnEnter(PlayerState::Swim), face_swim_direction)
             .add_systems(Update, update_player_state)
             .add_systems(Update, camera_follow_player)
-            .add_systems(Update, handle_player_input)
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    handle_player_input.run_if(not(typing_seed)),
+                    stop_player.run_if(typing_seed),
+                )
+                    .in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            )
+            .add_systems(FixedUpdate, move_player)
+            .add_systems(
+                RunFixedMainLoop,
+                interpolate_player_transform.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            )
             .add_systems(Update, update_player_chunk_pos)
             .add_systems(Update, update_player_sprite);
     }
 }
 
-fn setup(
-    mut commands: Commands,
-    mut handle: ResMut<DefaultAtlasHandle>,
-    mut sheet: ResMut<DefaultSpriteSheet>,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-) {
-    let texture_handle: Handle<Image> = asset_server.load(SPRITE_SHEET_PATH);
-    sheet.0 = Some(texture_handle);
-    let texture_atlas = TextureAtlasLayout::from_grid(
-        uvec2(TILE_W as u32, TILE_H as u32),
-        SPRITE_SHEET_W as u32,
-        SPRITE_SHEET_H as u32,
-        Some(UVec2::splat(SPRITE_PADDING)),
-        Some(UVec2::splat(SPRITE_SHEET_OFFSET)),
-    );
-    let texture_atlas_handle = texture_atlases.add(texture_atlas);
-    handle.0 = Some(texture_atlas_handle);
-
+fn setup(mut commands: Commands, atlas: Res<SpriteAtlas>) {
     commands.spawn((
-        Sprite::from_atlas_image(
-            sheet.0.clone().unwrap(),
-            TextureAtlas {
-                layout: handle.0.clone().unwrap(),
-                index: PLAYER_SPRITE_INDEX,
-            },
-        ),
+        atlas.sprite(0),
         Transform::from_scale(Vec3::splat(SPRITE_SCALE_FACTOR as f32))
             .with_translation(vec3(0.0, 0.0, 2.0)),
         Player,
+        Velocity::default(),
+        PhysicalTranslation::default(),
+        PreviousPhysicalTranslation::default(),
+        JumpTimer(Timer::from_seconds(PLAYER_JUMP_TIME, TimerMode::Once)),
         AnimationTimer(Timer::from_seconds(
             PLAYER_ANIMATION_INTERVAL,
             TimerMode::Repeating,
@@ -114,46 +115,84 @@ fn setup(
 }
 
 fn update_player_state(
+    time: Res<Time>,
     player_state: Res<State<PlayerState>>,
     mut next_player_state: ResMut<NextState<PlayerState>>,
-    mut sprite_index: ResMut<PlayerSpriteIndex>,
-    ground_tiles: Res<GroundTiles>,
-    player_query: Query<&Transform, With<Player>>,
+    world_tiles: Res<WorldTiles>,
+    mut player_query: Query<(&PhysicalTranslation, &mut JumpTimer), With<Player>>,
 ) {
-    if player_query.is_empty() {
+    let Ok((translation, mut jump_timer)) = player_query.single_mut() else {
+        return;
+    };
+
+    let pos = WorldPos::from(translation.0).to_grid();
+    let is_ground = world_tiles
+        .tile_at(pos)
+        .is_some_and(|t| t.terrain.is_land());
+
+    if player_state.jumping() {
+        if jump_timer.tick(time.delta()).finished() {
+            next_player_state.set(if is_ground {
+                PlayerState::Idle
+            } else {
+                PlayerState::Swim
+            });
+        }
         return;
     }
 
-    let transform = player_query.single().unwrap();
-    let (x, y) = (transform.translation.x, transform.translation.y);
-    let (x, y) = world_to_grid(x, y);
-    let (x, y) = center_to_top_left_grid(x, y);
-    let is_ground = ground_tiles.0.contains(&(x as i32, y as i32));
+    // Crossing the shore either way is a jump
+    if is_ground != player_state.on_land() {
+        next_player_state.set(PlayerState::Jump);
+    }
+}
 
-    if !is_ground && player_state.on_land() {
-        next_player_state.set(PlayerState::Jump(Instant::now()));
+fn start_jump(
+    mut sprite_index: ResMut<PlayerSpriteIndex>,
+    mut player_query: Query<&mut JumpTimer, With<Player>>,
+) {
+    sprite_index.0 = 0;
+    for mut jump_timer in player_query.iter_mut() {
+        jump_timer.reset();
     }
-    if is_ground && player_state.swimming() {
-        next_player_state.set(PlayerState::Jump(Instant::now()));
+}
+
+fn land_jump(mut sprite_index: ResMut<PlayerSpriteIndex>) {
+    sprite_index.0 = 0;
+}
+
+/// Back on land the sprite stays upright, walking doesn't turn it
+fn stand_upright(mut player_query: Query<&mut Transform, With<Player>>) {
+    for mut transform in player_query.iter_mut() {
+        transform.rotation = Quat::IDENTITY;
     }
+}
 
-    match player_state.get() {
-        PlayerState::Jump(jumped_at) => {
-            if jumped_at.elapsed().as_secs_f32() > PLAYER_JUMP_TIME {
-                next_player_state.set(if is_ground {
-                    PlayerState::Idle
-                } else {
-                    PlayerState::Swim
-                });
-                sprite_index.0 = 0;
-            }
-        }
-        _ => {}
+/// The first step is taken a whole interval after setting off, like every other
+fn start_walk(mut player_query: Query<&mut AnimationTimer, With<Player>>) {
+    for mut timer in player_query.iter_mut() {
+        timer.reset();
+    }
+}
+
+/// Stopping puts both feet down rather than freezing mid step
+fn stop_walk(mut sprite_index: ResMut<PlayerSpriteIndex>) {
+    sprite_index.0 = 0;
+}
+
+/// In water the sprite faces the way the player last moved
+fn face_swim_direction(
+    player_direction: Res<PlayerDirection>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    for mut transform in player_query.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(player_direction.0);
     }
 }
 
 fn update_player_sprite(
     time: Res<Time>,
+    atlas: Res<SpriteAtlas>,
     mut sprite_index: ResMut<PlayerSpriteIndex>,
     player_state: Res<State<PlayerState>>,
     mut query: Query<(&mut Sprite, &mut AnimationTimer), With<Player>>,
@@ -161,75 +200,74 @@ fn update_player_sprite(
     if query.is_empty() {
         return;
     }
+    let Some(sprites) = atlas.sprites.as_ref() else {
+        return;
+    };
 
     let (mut sprite, mut timer) = query.single_mut().unwrap();
     timer.tick(time.delta());
 
+    let walk = sprites.get("player.walk");
+    let jump = sprites.get("player.jump");
     if player_state.walking() && timer.finished() {
-        sprite_index.0 = (sprite_index.0 + 1) % 3;
+        sprite_index.0 = (sprite_index.0 + 1) % walk.frames;
     }
     if player_state.jumping() && timer.finished() {
-        sprite_index.0 = (sprite_index.0 + 1) % 3;
+        sprite_index.0 = (sprite_index.0 + 1) % jump.frames;
     }
 
     sprite.texture_atlas.as_mut().unwrap().index = if player_state.on_land() {
-        sprite_index.0 + PLAYER_SPRITE_INDEX
+        walk.frame(sprite_index.0)
     } else if player_state.jumping() {
-        sprite_index.0 + PLAYER_SPRITE_INDEX + 3
+        jump.frame(sprite_index.0)
     } else {
-        49
+        sprites.get("player.swim").cell
     };
 }
 
 fn update_player_chunk_pos(
     mut chunk_position: ResMut<CurrentPlayerChunkPosition>,
-    mut chunk_update_event: EventWriter<PlayerChunkUpdateEvent>,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<&PhysicalTranslation, With<Player>>,
 ) {
     if player_query.is_empty() {
         return;
     }
 
-    let transform = player_query.single().unwrap();
-    let (x, y) = (transform.translation.x, transform.translation.y);
-    let (a, b) = world_to_grid(x, y);
-    let (a, b) = center_to_top_left_grid(a, b);
-    let (x, y) = grid_to_chunk(a, b);
-
-    let (old_x, old_y) = chunk_position.0;
-    if old_x == x && old_y == y {
-        return;
+    let translation = player_query.single().unwrap();
+    let chunk = WorldPos::from(translation.0).to_chunk();
+    // Only written on change, terrain loads around the player when it is
+    if chunk_position.0 != chunk {
+        chunk_position.0 = chunk;
     }
-
-    chunk_update_event.write(PlayerChunkUpdateEvent((x, y)));
-    chunk_position.0 = (x, y);
 }
 
 fn handle_player_input(
     player_state: Res<State<PlayerState>>,
     mut next_player_state: ResMut<NextState<PlayerState>>,
     mut player_direction: ResMut<PlayerDirection>,
-    mut player_query: Query<&mut Transform, With<Player>>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
     keys: Res<ButtonInput<KeyCode>>,
 ) {
     if player_query.is_empty() {
         return;
     }
+
+    let (mut transform, mut velocity) = player_query.single_mut().unwrap();
+    velocity.0 = Vec2::ZERO;
     if player_state.jumping() {
         return;
     }
 
-    let mut transform = player_query.single_mut().unwrap();
     let w_key = keys.pressed(KeyCode::KeyW);
     let a_key = keys.pressed(KeyCode::KeyA);
     let s_key = keys.pressed(KeyCode::KeyS);
     let d_key = keys.pressed(KeyCode::KeyD);
     let speed_scale = if keys.pressed(KeyCode::ShiftLeft) {
-        5.0
+        PLAYER_SPRINT_SCALE
     } else {
         1.0
     };
-    let mut direction = Vec3::ZERO;
+    let mut direction = Vec2::ZERO;
 
     if w_key {
         direction.y += 1.0;
@@ -256,39 +294,98 @@ fn handle_player_input(
         } else {
             PLAYER_FISH_SPEED
         };
-        let new_pos = transform.translation + direction.normalize() * speed * speed_scale;
-
-        if !new_pos.is_nan() {
-            transform.translation = new_pos;
-        }
+        velocity.0 = direction.normalize_or_zero() * speed * speed_scale;
 
         transform.rotation = Quat::from_rotation_z(sprite_angle);
         player_direction.0 = player_angle;
-         next_player_state.set(if player_state.on_land() {
+        next_player_state.set(if player_state.on_land() {
             PlayerState::Walk
         } else {
             PlayerState::Swim
         });
-    } 
+    } else if player_state.walking() {
+        next_player_state.set(PlayerState::Idle);
+    }
+}
+
+/// Keeps the player still while the keyboard types elsewhere
+fn stop_player(mut player_query: Query<&mut Velocity, With<Player>>) {
+    for mut velocity in player_query.iter_mut() {
+        velocity.0 = Vec2::ZERO;
+    }
+}
+
+/// Steps the player along its velocity, as far each second whatever the frame rate
+fn move_player(
+    time: Res<Time>,
+    settings: Res<WorldGenSettings>,
+    mut player_query: Query<
+        (
+            &Velocity,
+            &mut PhysicalTranslation,
+            &mut PreviousPhysicalTranslation,
+        ),
+        With<Player>,
+    >,
+) {
+    for (velocity, mut current, mut previous) in player_query.iter_mut() {
+        previous.0 = current.0;
+        let mut next = current.0 + velocity.0 * time.delta_secs();
+        if let Some(rect) = settings.bounds.world_rect() {
+            next = next.clamp(rect.min, rect.max);
+        }
+        current.0 = next;
+    }
+}
+
+/// Draws the player between the last two fixed steps
+fn interpolate_player_transform(
+    fixed_time: Res<Time<Fixed>>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &PhysicalTranslation,
+            &PreviousPhysicalTranslation,
+        ),
+        With<Player>,
+    >,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, current, previous) in player_query.iter_mut() {
+        let translation = previous.0.lerp(current.0, alpha);
+        transform.translation = translation.extend(transform.translation.z);
+    }
 }
 
 fn camera_follow_player(
-    mut camera_query: Query<(&Camera, &mut Transform), Without<Player>>,
+    time: Res<Time>,
+    settings: Res<WorldGenSettings>,
+    mut camera_query: Query<(&Camera, &Projection, &mut Transform), Without<Player>>,
     player_query: Query<&Transform, With<Player>>,
 ) {
     if player_query.is_empty() {
         return;
     }
 
-    let (_, mut camera_transform) = camera_query.single_mut().unwrap();
+    let (_, projection, mut camera_transform) = camera_query.single_mut().unwrap();
     let player_transform = player_query.single().unwrap();
 
-    camera_transform.translation = camera_transform.translation.lerp(
-        vec3(
-            player_transform.translation.x,
-            player_transform.translation.y,
-            0.0,
-        ),
-        0.05,
-    );
+    // Stop where the view would show past the edge of the world, a world
+    // smaller than the view stays centred
+    let mut target = player_transform.translation.xy();
+    if let Some(rect) = settings.bounds.world_rect() {
+        let half_view = match projection {
+            Projection::Orthographic(ortho) => ortho.area.half_size(),
+            _ => Vec2::ZERO,
+        };
+        let min = (rect.min + half_view).min(rect.center());
+        let max = (rect.max - half_view).max(rect.center());
+        target = target.clamp(min, max);
+    }
+
+    // The same share of the way each second whatever the frame rate
+    let follow = 1.0 - (-CAMERA_FOLLOW_RATE * time.delta_secs()).exp();
+    camera_transform.translation = camera_transform
+        .translation
+        .lerp(target.extend(0.0), follow);
 }
@@ -0,0 +1,112 @@
+use crate::*;
+use hydrology::RiverCache;
+use settlement::plan_village;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use worldgen::{Elevation, WorldGenSettings};
+
+/// Roads may stray this many tiles outside the box around the villages they join
+pub const ROAD_MARGIN: i32 = 24;
+
+/// A road to search for between the markets of two neighbouring villages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoadPlan {
+    pub from: GridPos,
+    pub to: GridPos,
+    /// The road stays within `min..max`
+    pub min: GridPos,
+    pub max: GridPos,
+}
+
+/// Roads that may pass through `min..max`, joining the villages of
+/// squares next to each other
+pub fn roads_near(
+    gen_seed: u32,
+    elevation: &Elevation,
+    rivers: &RiverCache,
+    settings: &WorldGenSettings,
+    min: GridPos,
+    max: GridPos,
+) -> Vec<RoadPlan> {
+    let spacing = settings.settlements.spacing;
+    let cells = |from: i32, to: i32| {
+        (from - spacing - ROAD_MARGIN).div_euclid(spacing)..=(to + ROAD_MARGIN).div_euclid(spacing)
+    };
+    let mut centers = HashMap::new();
+    let mut center = |cell: (i32, i32)| {
+        *centers.entry(cell).or_insert_with(|| {
+            plan_village(gen_seed, elevation, rivers, settings, cell).map(|v| v.settlement.center)
+        })
+    };
+
+    let mut roads = Vec::new();
+    for cx in cells(min.x, max.x) {
+        for cy in cells(min.y, max.y) {
+            for (i, j) in [(1, 0), (0, 1)] {
+                let (Some(from), Some(to)) = (center((cx, cy)), center((cx + i, cy + j))) else {
+                    continue;
+                };
+                let road = RoadPlan {
+                    from,
+                    to,
+                    min: GridPos::new(from.x.min(to.x), from.y.min(to.y))
+                        .offset(-ROAD_MARGIN, -ROAD_MARGIN),
+                    max: GridPos::new(from.x.max(to.x), from.y.max(to.y))
+                        .offset(ROAD_MARGIN + 1, ROAD_MARGIN + 1),
+                };
+                if road.min.x < max.x
+                    && road.max.x > min.x
+                    && road.min.y < max.y
+                    && road.max.y > min.y
+                {
+                    roads.push(road);
+                }
+            }
+        }
+    }
+    roads
+}
+
+/// Cheapest way along the road's plan, stepping to the four neighbours. `cost` is the
+/// price of stepping onto a tile, at least 1, `None` where roads can't go.
+pub fn find_road(
+    road: &RoadPlan,
+    mut cost: impl FnMut(GridPos) -> Option<u32>,
+) -> Option<Vec<GridPos>> {
+    let inside = |p: GridPos| {
+        (road.min.x..road.max.x).contains(&p.x) && (road.min.y..road.max.y).contains(&p.y)
+    };
+    let estimate = |p: GridPos| p.x.abs_diff(road.to.x) + p.y.abs_diff(road.to.y);
+
+    let mut spent = HashMap::from([(road.from, 0)]);
+    let mut came_from = HashMap::new();
+    let mut open = BinaryHeap::from([Reverse((estimate(road.from), road.from))]);
+    while let Some(Reverse((_, pos))) = open.pop() {
+        if pos == road.to {
+            let mut path = vec![pos];
+            while let Some(prev) = came_from.get(path.last().unwrap()) {
+                path.push(*prev);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let here = spent[&pos];
+        for (i, j) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let next = pos.offset(i, j);
+            if !inside(next) {
+                continue;
+            }
+            let Some(step) = cost(next) else {
+                continue;
+            };
+            let total = here + step;
+            if spent.get(&next).is_none_or(|s| total < *s) {
+                spent.insert(next, total);
+                came_from.insert(next, pos);
+                open.push(Reverse((total + estimate(next), next)));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,52 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+
+/// Loads any deserializable asset from a RON file with one of the given extensions
+pub struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    validate: fn(&A) -> Result<(), String>,
+    _asset: PhantomData<fn() -> A>,
+}
+
+impl<A> RonAssetLoader<A> {
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            validate: |_| Ok(()),
+            _asset: PhantomData,
+        }
+    }
+
+    /// Fails the load with the returned error when the file parses but isn't usable
+    pub fn with_validation(mut self, validate: fn(&A) -> Result<(), String>) -> Self {
+        self.validate = validate;
+        self
+    }
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let asset = ron::de::from_bytes(&bytes)?;
+        (self.validate)(&asset)?;
+        Ok(asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -0,0 +1,197 @@
+use crate::*;
+use bevy::prelude::*;
+use chunk_delta::ChunkDeltas;
+use player::{
+    CurrentPlayerChunkPosition, PhysicalTranslation, Player, PlayerState,
+    PreviousPhysicalTranslation,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+use terrain::{GenerationSeed, ResetTerrainEvent};
+use worldgen::WorldGenSettings;
+
+pub struct SavePlugin {
+    /// Save file to resume from as soon as the game starts, saves are written back to it
+    pub load: Option<PathBuf>,
+}
+
+#[derive(Resource)]
+pub struct SavePath(pub PathBuf);
+#[derive(Event)]
+pub struct SaveGameEvent;
+#[derive(Event)]
+pub struct LoadGameEvent;
+
+/// Everything needed to resume a session
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    pub seed: u32,
+    pub player: SavedPlayer,
+    pub chunk: ChunkPos,
+    #[serde(default)]
+    pub chunk_deltas: ChunkDeltas,
+    /// Saves from before world settings existed were all classic worlds
+    #[serde(default)]
+    pub world_gen: WorldGenSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedPlayer {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub state: SavedPlayerState,
+}
+
+/// `PlayerState` as written to saves, a loaded jump starts over
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SavedPlayerState {
+    Idle,
+    Walk,
+    Jump,
+    Swim,
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let path = self.load.clone().unwrap_or_else(|| SAVE_PATH.into());
+        app.insert_resource(SavePath(path))
+            .add_event::<SaveGameEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_systems(Update, (handle_save_game_event, handle_load_game_event));
+
+        if self.load.is_some() {
+            app.add_systems(Startup, |mut writer: EventWriter<LoadGameEvent>| {
+                writer.write(LoadGameEvent);
+            });
+        }
+    }
+}
+
+impl SaveGame {
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+impl From<&PlayerState> for SavedPlayerState {
+    fn from(state: &PlayerState) -> Self {
+        match state {
+            PlayerState::Idle => SavedPlayerState::Idle,
+            PlayerState::Walk => SavedPlayerState::Walk,
+            PlayerState::Jump => SavedPlayerState::Jump,
+            PlayerState::Swim => SavedPlayerState::Swim,
+        }
+    }
+}
+
+impl From<SavedPlayerState> for PlayerState {
+    fn from(state: SavedPlayerState) -> Self {
+        match state {
+            SavedPlayerState::Idle => PlayerState::Idle,
+            SavedPlayerState::Walk => PlayerState::Walk,
+            SavedPlayerState::Jump => PlayerState::Jump,
+            SavedPlayerState::Swim => PlayerState::Swim,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_save_game_event(
+    mut reader: EventReader<SaveGameEvent>,
+    path: Res<SavePath>,
+    seed: Res<GenerationSeed>,
+    settings: Res<WorldGenSettings>,
+    deltas: Res<ChunkDeltas>,
+    chunk_pos: Res<CurrentPlayerChunkPosition>,
+    player_state: Res<State<PlayerState>>,
+    player_query: Query<(&Transform, &PhysicalTranslation), With<Player>>,
+) {
+    if reader.is_empty() {
+        return;
+    }
+    reader.clear();
+
+    let Ok((transform, physical)) = player_query.single() else {
+        return;
+    };
+
+    let save = SaveGame {
+        seed: seed.0,
+        player: SavedPlayer {
+            translation: physical.extend(transform.translation.z).to_array(),
+            rotation: transform.rotation.to_array(),
+            state: player_state.get().into(),
+        },
+        chunk: chunk_pos.0,
+        chunk_deltas: deltas.clone(),
+        world_gen: settings.clone(),
+    };
+
+    match save.write(&path.0) {
+        Ok(()) => info!("saved world to {}", path.0.display()),
+        Err(e) => error!("failed to save world to {}: {e}", path.0.display()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_load_game_event(
+    mut reader: EventReader<LoadGameEvent>,
+    mut reset_writer: EventWriter<ResetTerrainEvent>,
+    path: Res<SavePath>,
+    mut deltas: ResMut<ChunkDeltas>,
+    mut settings: ResMut<WorldGenSettings>,
+    mut chunk_pos: ResMut<CurrentPlayerChunkPosition>,
+    mut next_player_state: ResMut<NextState<PlayerState>>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut PhysicalTranslation,
+            &mut PreviousPhysicalTranslation,
+        ),
+        With<Player>,
+    >,
+) {
+    if reader.is_empty() {
+        return;
+    }
+    reader.clear();
+
+    let Ok((mut transform, mut physical, mut previous)) = player_query.single_mut() else {
+        return;
+    };
+
+    let save = match SaveGame::read(&path.0) {
+        Ok(save) => save,
+        Err(e) => {
+            error!("failed to load world from {}: {e}", path.0.display());
+            return;
+        }
+    };
+
+    transform.translation = Vec3::from_array(save.player.translation);
+    // Set both so the player isn't drawn sliding over from where it was
+    physical.0 = transform.translation.truncate();
+    previous.0 = physical.0;
+    transform.rotation = Quat::from_array(save.player.rotation);
+    next_player_state.set(save.player.state.into());
+    chunk_pos.0 = save.chunk;
+    *deltas = save.chunk_deltas;
+    settings.set_if_neq(save.world_gen);
+
+    // Regenerates the world around the restored chunk
+    reset_writer.write(ResetTerrainEvent(Some(save.seed)));
+    info!("loaded world from {}", path.0.display());
+}
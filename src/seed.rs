@@ -0,0 +1,173 @@
+use crate::*;
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+use chunk_delta::ChunkDeltas;
+use terrain::{GenerationSeed, ResetTerrainEvent};
+
+pub const MAX_SEED_LEN: usize = 32;
+
+pub struct SeedPlugin;
+
+/// Text typed into the new world screen, `None` while it's closed
+#[derive(Resource, Default)]
+pub struct SeedEntry(pub Option<String>);
+
+#[derive(Component)]
+struct SeedLabel;
+#[derive(Component)]
+struct NewWorldScreen;
+#[derive(Component)]
+struct SeedField;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedEntry>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (type_seed, toggle_new_world_screen, update_seed_ui).chain(),
+            );
+    }
+}
+
+/// Seed of a world given as text. Numbers are taken as they are, so the seed shown
+/// in game can be typed back in, anything else is hashed.
+pub fn seed_from_text(text: &str) -> u32 {
+    let text = text.trim();
+    if let Ok(seed) = text.parse() {
+        return seed;
+    }
+
+    // FNV-1a, unlike std's hasher it stays the same across builds
+    text.bytes().fold(0x811c9dc5, |hash: u32, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+/// Run condition, true while the new world screen takes the keyboard
+pub fn typing_seed(entry: Option<Res<SeedEntry>>) -> bool {
+    entry.is_some_and(|entry| entry.0.is_some())
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextFont::from_font_size(18.0),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        SeedLabel,
+    ));
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(40.0),
+                left: Val::Percent(30.0),
+                width: Val::Percent(40.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+            Visibility::Hidden,
+            NewWorldScreen,
+        ))
+        .with_children(|screen| {
+            screen.spawn((
+                Text::new("New world, type a seed or leave it empty for a random one"),
+                TextFont::from_font_size(18.0),
+            ));
+            screen.spawn((Text::default(), TextFont::from_font_size(22.0), SeedField));
+            screen.spawn((
+                Text::new("Enter to generate, F2 to cancel"),
+                TextFont::from_font_size(14.0),
+            ));
+        });
+}
+
+fn toggle_new_world_screen(keys: Res<ButtonInput<KeyCode>>, mut entry: ResMut<SeedEntry>) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+
+    entry.0 = match entry.0 {
+        Some(_) => None,
+        None => Some(String::new()),
+    };
+}
+
+fn type_seed(
+    mut keyboard: EventReader<KeyboardInput>,
+    mut entry: ResMut<SeedEntry>,
+    mut deltas: ResMut<ChunkDeltas>,
+    mut writer: EventWriter<ResetTerrainEvent>,
+) {
+    // Keys pressed before the screen opened aren't part of the seed
+    if entry.0.is_none() {
+        keyboard.clear();
+        return;
+    }
+    let Some(text) = entry.0.as_mut() else {
+        return;
+    };
+
+    for ev in keyboard.read() {
+        if ev.state == ButtonState::Released {
+            continue;
+        }
+        match &ev.logical_key {
+            Key::Character(c) if text.len() + c.len() <= MAX_SEED_LEN => text.push_str(c),
+            Key::Space if text.len() < MAX_SEED_LEN => text.push(' '),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Enter => {
+                let seed = (!text.trim().is_empty()).then(|| seed_from_text(text));
+                // Edits belong to the world they were made in
+                deltas.0.clear();
+                writer.write(ResetTerrainEvent(seed));
+                entry.0 = None;
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_seed_ui(
+    seed: Res<GenerationSeed>,
+    entry: Res<SeedEntry>,
+    mut label_q: Query<&mut Text, (With<SeedLabel>, Without<SeedField>)>,
+    mut field_q: Query<&mut Text, (With<SeedField>, Without<SeedLabel>)>,
+    mut screen_q: Query<&mut Visibility, With<NewWorldScreen>>,
+) {
+    if seed.is_changed() {
+        for mut label in label_q.iter_mut() {
+            label.0 = format!("Seed: {}", seed.0);
+        }
+    }
+    if !entry.is_changed() {
+        return;
+    }
+
+    for mut visibility in screen_q.iter_mut() {
+        *visibility = match entry.0 {
+            Some(_) => Visibility::Visible,
+            None => Visibility::Hidden,
+        };
+    }
+    for mut field in field_q.iter_mut() {
+        field.0 = format!("> {}_", entry.0.as_deref().unwrap_or_default());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::*;
+use bevy::prelude::*;
+use hydrology::RiverCache;
+use rand::{seq::IndexedRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use world::{Feature, HouseSize};
+use worldgen::{Elevation, WorldGenSettings};
+
+const NAME_STARTS: [&str; 16] = [
+    "Ash", "Oak", "Stone", "Mill", "Elm", "Thorn", "Brook", "Wolf", "Raven", "High", "Low",
+    "Green", "Red", "Black", "Fair", "Cold",
+];
+const NAME_ENDS: [&str; 12] = [
+    "ford", "ton", "by", "wick", "field", "ham", "stead", "bridge", "dale", "worth", "mere", "holt",
+];
+
+/// Where villages are founded and how far they sprawl
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SettlementSettings {
+    /// The world is split into squares this many tiles wide, each with at most one village
+    pub spacing: i32,
+    pub chance: f64,
+    /// Longest street from the village centre
+    pub max_street: i32,
+    /// Villages are built on dry, flat ground between these elevations
+    pub elevation: (f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Good {
+    Food,
+    Wood,
+    Stone,
+    Clay,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stockpile(pub HashMap<Good, u32>);
+
+/// A village the player can trade with
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub name: String,
+    /// Tile of the market
+    pub center: GridPos,
+    pub population: u32,
+    pub stockpile: Stockpile,
+}
+
+/// A settlement along with the features of the tiles it's built on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Village {
+    pub settlement: Settlement,
+    /// Feature and sprite variant of every tile
+    pub tiles: HashMap<GridPos, (Feature, u8)>,
+    /// Roads leading out of the plaza
+    pub streets: HashSet<GridPos>,
+}
+
+impl Default for SettlementSettings {
+    fn default() -> Self {
+        Self {
+            spacing: 160,
+            chance: 0.7,
+            max_street: 10,
+            elevation: (0.05, 0.35),
+        }
+    }
+}
+
+impl SettlementSettings {
+    /// How far a village's tiles reach from its centre, fields lie behind the houses
+    pub fn reach(&self) -> i32 {
+        self.max_street + 3
+    }
+}
+
+/// Villages with tiles in `min..max`. Like rivers every village is planned from
+/// the seed, elevation and rivers alone, so neighbouring chunks agree on them.
+pub fn villages_near(
+    gen_seed: u32,
+    elevation: &Elevation,
+    rivers: &RiverCache,
+    settings: &WorldGenSettings,
+    min: GridPos,
+    max: GridPos,
+) -> Vec<Village> {
+    let reach = settings.settlements.reach();
+    let spacing = settings.settlements.spacing;
+    let mut villages = Vec::new();
+    for cx in (min.x - reach).div_euclid(spacing)..=(max.x + reach).div_euclid(spacing) {
+        for cy in (min.y - reach).div_euclid(spacing)..=(max.y + reach).div_euclid(spacing) {
+            let Some(village) = plan_village(gen_seed, elevation, rivers, settings, (cx, cy))
+            else {
+                continue;
+            };
+            let center = village.settlement.center;
+            if center.x + reach >= min.x
+                && center.x - reach < max.x
+                && center.y + reach >= min.y
+                && center.y - reach < max.y
+            {
+                villages.push(village);
+            }
+        }
+    }
+    villages
+}
+
+/// The village of a square of the world, if it has one. A plaza with the market
+/// in the middle, streets leading out of it lined by houses, and fields behind the houses.
+pub fn plan_village(
+    gen_seed: u32,
+    elevation: &Elevation,
+    rivers: &RiverCache,
+    world: &WorldGenSettings,
+    (cx, cy): (i32, i32),
+) -> Option<Village> {
+    let settings = &world.settlements;
+    let mut rng_seed = [0; 32];
+    rng_seed[0..4].copy_from_slice(&gen_seed.to_le_bytes());
+    rng_seed[4..8].copy_from_slice(&cx.to_le_bytes());
+    rng_seed[8..12].copy_from_slice(&cy.to_le_bytes());
+    // Keeps these rolls apart from rivers and chunks at the same coordinates
+    rng_seed[12] = 2;
+    let mut rng = ChaCha8Rng::from_seed(rng_seed);
+
+    if rng.random_range(0.0..1.0) >= settings.chance {
+        return None;
+    }
+    // Kept off the edges so villages of neighbouring squares never overlap
+    let (spacing, reach) = (settings.spacing, settings.reach());
+    let room = (spacing - 2 * reach).max(1);
+    let center = GridPos::new(
+        cx * spacing + reach + rng.random_range(0..room),
+        cy * spacing + reach + rng.random_range(0..room),
+    );
+    let (low, high) = settings.elevation;
+    let flat = |p: GridPos| (low..high).contains(&elevation.get([p.x as f64, p.y as f64]));
+    if !flat(center) {
+        return None;
+    }
+    // Nothing is built over rivers and lakes, streets end at their banks
+    let water = rivers.fresh_water(
+        gen_seed,
+        elevation,
+        &world.rivers,
+        center.offset(-reach, -reach),
+        center.offset(reach + 1, reach + 1),
+    );
+    let buildable = |p: GridPos| flat(p) && !water.contains_key(&p);
+    if !buildable(center) {
+        return None;
+    }
+
+    let mut tiles = HashMap::new();
+    for (i, j) in [(0, 0)].iter().chain(autotile::Neighbours::OFFSETS.iter()) {
+        let pos = center.offset(*i, *j);
+        if buildable(pos) {
+            tiles.insert(pos, (Feature::SettlementGround, 0));
+        }
+    }
+    tiles.insert(center, (Feature::Market, 0));
+
+    let mut streets = HashSet::new();
+    let (mut population, mut fields) = (0, 0);
+    for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+        let length = rng.random_range(2..=settings.max_street);
+        for step in 2..=length {
+            let street = center.offset(dx * step, dy * step);
+            if !buildable(street) {
+                break;
+            }
+            streets.insert(street);
+
+            // Both sides of the street, across it
+            for side in [-1, 1] {
+                let (sx, sy) = (dy * side, dx * side);
+                let lot = street.offset(sx, sy);
+                // Streets of different arms never cross, only the plaza is in the way
+                if buildable(lot) && !tiles.contains_key(&lot) && rng.random_bool(0.6) {
+                    let size = if rng.random_bool(0.25) {
+                        HouseSize::Large
+                    } else {
+                        HouseSize::Small
+                    };
+                    tiles.insert(lot, (Feature::House(size), rng.random()));
+                    population += size.residents();
+                }
+                for depth in 2..=3 {
+                    let field = street.offset(sx * depth, sy * depth);
+                    if buildable(field) && !tiles.contains_key(&field) {
+                        tiles.insert(field, (Feature::Field, rng.random()));
+                        fields += 1;
+                    }
+                }
+            }
+        }
+    }
+    // A market and a couple of houses aren't worth trading with
+    if population < 12 {
+        return None;
+    }
+
+    let name = format!(
+        "{}{}",
+        NAME_STARTS.choose(&mut rng).unwrap(),
+        NAME_ENDS.choose(&mut rng).unwrap()
+    );
+    let stockpile = Stockpile(HashMap::from([
+        (Good::Food, fields * 4),
+        (Good::Wood, rng.random_range(20..60)),
+        (Good::Stone, rng.random_range(0..30)),
+        (Good::Clay, rng.random_range(0..20)),
+    ]));
+
+    Some(Village {
+        settlement: Settlement {
+            name,
+            center,
+            population,
+            stockpile,
+        },
+        tiles,
+        streets,
+    })
+}
@@ -1,43 +1,126 @@
-use bevy::prelude::Resource;
-use std::collections::HashSet;
+use bevy::math::{vec2, Vec2};
+use serde::{Deserialize, Serialize};
 
 use crate::*;
 
-#[derive(Resource)]
-pub struct GroundTiles(pub HashSet<(i32, i32)>);
+/// Position in world space, x grows to the right and y grows upwards
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WorldPos {
+    pub x: f32,
+    pub y: f32,
+}
 
-pub fn grid_to_world(x: f32, y: f32) -> (f32, f32) {
-    (
-        x * TILE_W as f32 * SPRITE_SCALE_FACTOR as f32,
-        y * TILE_H as f32 * SPRITE_SCALE_FACTOR as f32,
-    )
+/// Tile coordinate, x grows to the right and y grows downwards.
+/// Tile (0, 0) is the top left of the initial window, centred on the world origin.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GridPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Chunk coordinate, each chunk holds `CHUNK_W` x `CHUNK_H` tiles
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default,
+)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub y: i32,
 }
 
-pub fn world_to_grid(x: f32, y: f32) -> (f32, f32) {
+/// Tile coordinate relative to the top left tile of its chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocalTilePos {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Size of one tile in world units
+const fn tile_size() -> (f32, f32) {
     (
-        (x / (TILE_W as f32 * SPRITE_SCALE_FACTOR as f32)).floor(),
-        (y / (TILE_H as f32 * SPRITE_SCALE_FACTOR as f32)).floor(),
+        (TILE_W * SPRITE_SCALE_FACTOR) as f32,
+        (TILE_H * SPRITE_SCALE_FACTOR) as f32,
     )
 }
 
-pub fn center_to_top_left_grid(x: f32, y: f32) -> (f32, f32) {
-    let x_center = x + GRID_COLS as f32 / 2.0;
-    let y_center = GRID_ROWS as f32 / 2.0 - y;
-    (x_center, y_center)
+impl WorldPos {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Tile under this position, tiles own their left and top edges
+    pub fn to_grid(self) -> GridPos {
+        let (w, h) = tile_size();
+        GridPos::new(
+            (self.x / w + GRID_COLS as f32 / 2.0 + 0.5).floor() as i32,
+            (GRID_ROWS as f32 / 2.0 - self.y / h + 0.5).floor() as i32,
+        )
+    }
+
+    pub fn to_chunk(self) -> ChunkPos {
+        self.to_grid().to_chunk()
+    }
+}
+
+impl GridPos {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Centre of the tile
+    pub fn to_world(self) -> WorldPos {
+        let (w, h) = tile_size();
+        WorldPos::new(
+            (self.x as f32 - GRID_COLS as f32 / 2.0) * w,
+            (GRID_ROWS as f32 / 2.0 - self.y as f32) * h,
+        )
+    }
+
+    pub fn to_chunk(self) -> ChunkPos {
+        ChunkPos::new(
+            self.x.div_euclid(CHUNK_W as i32),
+            self.y.div_euclid(CHUNK_H as i32),
+        )
+    }
+
+    pub fn to_local(self) -> LocalTilePos {
+        LocalTilePos {
+            x: self.x.rem_euclid(CHUNK_W as i32) as u32,
+            y: self.y.rem_euclid(CHUNK_H as i32) as u32,
+        }
+    }
+
+    pub const fn offset(self, dx: i32, dy: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
 }
 
-pub fn center_to_top_left(x: f32, y: f32) -> (f32, f32) {
-    let x_center = x - (GRID_W as f32 * SPRITE_SCALE_FACTOR as f32) / 2.0;
-    let y_center = (GRID_H as f32 * SPRITE_SCALE_FACTOR as f32) / 2.0 - y;
-    (x_center, y_center)
+impl ChunkPos {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    /// Grid position of the top left tile
+    pub const fn origin(self) -> GridPos {
+        GridPos::new(self.x * CHUNK_W as i32, self.y * CHUNK_H as i32)
+    }
+
+    pub const fn tile(self, local: LocalTilePos) -> GridPos {
+        self.origin().offset(local.x as i32, local.y as i32)
+    }
+
+    pub const fn offset(self, dx: i32, dy: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
 }
 
-pub fn grid_to_chunk(x: f32, y: f32) -> (i32, i32) {
-    let (x, y) = (x / CHUNK_W as f32, y / CHUNK_H as f32);
-    (x.floor() as i32, y.floor() as i32)
+impl From<Vec2> for WorldPos {
+    fn from(v: Vec2) -> Self {
+        Self::new(v.x, v.y)
+    }
 }
 
-pub fn world_to_chunk(x: f32, y: f32) -> (i32, i32) {
-    let (x, y) = world_to_grid(x, y);
-    grid_to_chunk(x, y)
+impl From<WorldPos> for Vec2 {
+    fn from(p: WorldPos) -> Self {
+        vec2(p.x, p.y)
+    }
 }
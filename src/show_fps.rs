@@ -6,7 +6,7 @@ pub struct ShowFPSPlugin;
 impl Plugin for ShowFPSPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(FpsOverlayPlugin {
-        config: FpsOverlayConfig {
+            config: FpsOverlayConfig {
                 text_config: TextFont {
                     // Here we define size of our overlay
                     font_size: 22.0,
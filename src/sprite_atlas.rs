@@ -0,0 +1,178 @@
+use crate::*;
+use bevy::{math::uvec2, prelude::*};
+use chunk_mesh::atlas_uvs;
+use ron_asset::RonAssetLoader;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc};
+use world::Feature;
+
+pub struct SpriteAtlasPlugin;
+
+/// The sprite sheet and its layout, shared by everything that draws from it
+#[derive(Resource)]
+pub struct SpriteAtlas {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    pub manifest: Handle<SpriteRegistry>,
+    /// Normalised uv rect of every cell, available once the image is loaded
+    pub uvs: Option<Arc<[Rect]>>,
+    /// Available once the manifest is loaded
+    pub sprites: Option<Arc<SpriteRegistry>>,
+}
+
+/// Named cells of the sprite sheet, loaded from the atlas manifest
+#[derive(Asset, TypePath, Deserialize, Debug, Clone)]
+pub struct SpriteRegistry {
+    sprites: HashMap<String, SpriteEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteEntry {
+    pub cell: usize,
+    /// Number of consecutive cells holding variants or animation frames
+    #[serde(default = "single_frame")]
+    pub frames: usize,
+}
+
+/// Sprites drawn for terrain, roads and the player. A manifest missing any of them,
+/// or the sprite of any `Feature`, fails to load.
+pub const REQUIRED_SPRITES: [&str; 13] = [
+    "ground",
+    "water.fresh",
+    "shore.island",
+    "shore.tip",
+    "shore.inner",
+    "shore.top_left",
+    "shore.top_right",
+    "shore.bottom_left",
+    "shore.bottom_right",
+    "road",
+    "player.walk",
+    "player.jump",
+    "player.swim",
+];
+
+/// Every sprite the game draws
+pub fn required_sprites() -> impl Iterator<Item = &'static str> {
+    let features = Feature::ALL.into_iter().map(|f| f.sprite_name());
+    REQUIRED_SPRITES.into_iter().chain(features)
+}
+
+/// Drawn in place of a sprite the manifest doesn't have
+const MISSING_SPRITE: SpriteEntry = SpriteEntry { cell: 0, frames: 1 };
+
+fn single_frame() -> usize {
+    1
+}
+
+impl Plugin for SpriteAtlasPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpriteRegistry>()
+            .register_asset_loader(
+                RonAssetLoader::<SpriteRegistry>::new(&["atlas.ron"])
+                    .with_validation(SpriteRegistry::validate),
+            )
+            .add_systems(PreStartup, setup)
+            .add_systems(Update, (load_uvs, load_sprites));
+    }
+}
+
+impl SpriteAtlas {
+    pub fn sprite(&self, index: usize) -> Sprite {
+        Sprite::from_atlas_image(
+            self.image.clone(),
+            TextureAtlas {
+                layout: self.layout.clone(),
+                index,
+            },
+        )
+    }
+}
+
+impl SpriteRegistry {
+    pub fn from_ron(manifest: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(manifest)
+    }
+
+    /// Warns and falls back to the first cell if the manifest has no sprite with that
+    /// name, loaded manifests always have the `required_sprites`
+    pub fn get(&self, name: &str) -> SpriteEntry {
+        match self.sprites.get(name) {
+            Some(entry) => *entry,
+            None => {
+                warn!("sprite `{name}` is missing from {SPRITE_MANIFEST_PATH}");
+                MISSING_SPRITE
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let missing: Vec<_> = required_sprites()
+            .filter(|name| !self.sprites.contains_key(*name))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(format!("missing sprites {}", missing.join(", ")))
+    }
+}
+
+impl SpriteEntry {
+    /// Cell of the given frame, wrapping around the animation
+    pub fn frame(&self, i: usize) -> usize {
+        self.cell + i % self.frames
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    let layout = TextureAtlasLayout::from_grid(
+        uvec2(TILE_W as u32, TILE_H as u32),
+        SPRITE_SHEET_W as u32,
+        SPRITE_SHEET_H as u32,
+        Some(UVec2::splat(SPRITE_PADDING)),
+        Some(UVec2::splat(SPRITE_SHEET_OFFSET)),
+    );
+
+    commands.insert_resource(SpriteAtlas {
+        image: asset_server.load(SPRITE_SHEET_PATH),
+        layout: texture_atlases.add(layout),
+        manifest: asset_server.load(SPRITE_MANIFEST_PATH),
+        uvs: None,
+        sprites: None,
+    });
+}
+
+fn load_uvs(
+    images: Res<Assets<Image>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut atlas: ResMut<SpriteAtlas>,
+) {
+    if atlas.uvs.is_some() {
+        return;
+    }
+
+    // Uvs are relative to the real image size, so wait until it is loaded
+    let (Some(image), Some(layout)) = (images.get(&atlas.image), layouts.get(&atlas.layout)) else {
+        return;
+    };
+    atlas.uvs = Some(atlas_uvs(layout, image.size()).into());
+}
+
+fn load_sprites(
+    mut events: EventReader<AssetEvent<SpriteRegistry>>,
+    registries: Res<Assets<SpriteRegistry>>,
+    mut atlas: ResMut<SpriteAtlas>,
+) {
+    for ev in events.read() {
+        if !ev.is_loaded_with_dependencies(&atlas.manifest) && !ev.is_modified(&atlas.manifest) {
+            continue;
+        }
+        if let Some(registry) = registries.get(&atlas.manifest) {
+            atlas.sprites = Some(Arc::new(registry.clone()));
+        }
+    }
+}
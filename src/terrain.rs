@@ -1,322 +1,804 @@
 use crate::*;
+use autotile::{road_sprite, shore_sprite, Neighbours};
 use bevy::{
-    math::{uvec2, vec3},
     prelude::*,
-    time::common_conditions::on_timer,
+    tasks::{futures::check_ready, AsyncComputeTaskPool, Task},
 };
+use biome::{BiomeRegistry, Biomes, Climate};
+use chunk_delta::{ChunkDelta, ChunkDeltas, TileEditEvent};
+use chunk_mesh::{build_chunk_meshes, chunk_origin, ChunkLayerMesh};
+use fertility::{fertility_layer, Fertility, FertilityLayer, FERTILITY_WATER_REACH};
+use hydrology::RiverCache;
 use noise::{NoiseFn, Perlin};
-use player::{CurrentPlayerChunkPosition, PlayerChunkUpdateEvent};
-use rand::Rng;
-use std::{collections::{HashMap, HashSet}, time::Duration};
+use player::CurrentPlayerChunkPosition;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use roads::{find_road, roads_near};
+use settlement::{villages_near, Settlement, Village};
+use sprite_atlas::{SpriteAtlas, SpriteRegistry};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use world::*;
+use worldgen::{Elevation, WorldBounds, WorldGenSettings};
+
+pub const MAX_CHUNK_TASKS: usize = 4;
+pub const CHUNKS_SPAWNED_PER_FRAME: usize = 1;
+/// Loaded chunks are only dropped once they're this many chunks out of view,
+/// so going back and forth over a chunk border doesn't regenerate them
+pub const CHUNK_UNLOAD_MARGIN: i32 = 1;
 
 #[derive(Component)]
 struct TileComponent;
+/// One entity per chunk layer
+#[derive(Resource)]
+pub struct CurrentChunks(pub HashMap<ChunkPos, Vec<Entity>>);
 #[derive(Resource)]
-struct CurrentChunks(HashMap<(i32, i32), Vec<Entity>>);
+pub struct GenerationSeed(pub u32);
+#[derive(Resource, Default)]
+struct PendingChunks(VecDeque<ChunkPos>);
+#[derive(Resource, Default)]
+struct ChunkTasks(HashMap<ChunkPos, Task<ChunkTaskResult>>);
 #[derive(Resource)]
-struct GenerationSeed(u32);
+struct ChunkMaterial(Handle<ColorMaterial>);
+/// Rivers traced for the chunks of the current world
+#[derive(Resource, Default)]
+struct Rivers(Arc<RiverCache>);
+/// Chunks from `min` to `max`, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkRect {
+    pub min: ChunkPos,
+    pub max: ChunkPos,
+}
+/// Chunks that should be loaded, the ones on screen and those around the player
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ViewArea {
+    pub player: ChunkPos,
+    /// `None` without a camera
+    pub visible: Option<ChunkRect>,
+    /// Nothing past the edge of a bounded world is loaded
+    pub bounds: WorldBounds,
+}
+/// Regenerates the world with the given seed, or a random one
 #[derive(Event)]
-pub struct ResetTerrainEvent;
+pub struct ResetTerrainEvent(pub Option<u32>);
+
+/// One sprite drawn for a tile, derived from its `TileData`
+#[derive(Debug, Eq, PartialEq, Hash)]
+pub struct TileSprite {
+    pub pos: GridPos,
+    pub sprite: usize,
+    pub z_index: i32,
+}
 
-#[derive(Eq, PartialEq, Hash)]
-struct Tile {
-    pos: (i32, i32),
-    sprite: usize,
-    z_index: i32,
+struct ChunkTaskResult {
+    generated: GeneratedChunk,
+    layers: Vec<ChunkLayerMesh>,
 }
 
-pub struct TerrainPlugin;
+/// Everything generated for a chunk from the seed and settings
+pub struct GeneratedChunk {
+    pub data: ChunkData,
+    pub fertility: FertilityLayer,
+    /// Settlements whose market is in the chunk
+    pub settlements: Vec<Settlement>,
+}
+
+/// Noise fields every climate value is sampled from
+struct ClimateNoise {
+    elevation: Elevation,
+    patches: Perlin,
+    minerals: Perlin,
+    soil: Perlin,
+    temperature: Perlin,
+    moisture: Perlin,
+}
+
+pub struct TerrainPlugin {
+    /// World to start in, a random one if not given
+    pub seed: Option<u32>,
+}
 
 impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
         let mut rng = rand::rng();
-        app.insert_resource(GroundTiles(HashSet::new()))
+        app.init_resource::<WorldTiles>()
+            .init_resource::<Fertility>()
             .insert_resource(CurrentChunks(HashMap::new()))
-            .insert_resource(GenerationSeed(rng.random()))
-            .add_systems(Update, handle_terrain_reset_event)
-            .add_systems(Update, despawn_chunks)
+            .insert_resource(GenerationSeed(self.seed.unwrap_or_else(|| rng.random())))
+            .init_resource::<PendingChunks>()
+            .init_resource::<ChunkTasks>()
+            .init_resource::<Rivers>()
+            .init_resource::<ChunkDeltas>()
+            .init_resource::<WorldGenSettings>()
+            .init_resource::<ViewArea>()
+            .add_systems(Startup, setup_chunk_material)
             .add_systems(
                 Update,
-                clean_ground_tiles.run_if(on_timer(Duration::from_secs_f32(2.0))),
+                (regenerate_on_settings_change, handle_terrain_reset_event).chain(),
             )
-            .add_systems(Update, handle_player_chunk_update_event)
-            .add_event::<ResetTerrainEvent>();
+            .add_systems(
+                Update,
+                (
+                    update_view_area,
+                    despawn_chunks,
+                    queue_chunks_in_view,
+                    handle_tile_edit_event,
+                    start_chunk_tasks,
+                    spawn_generated_chunks,
+                )
+                    .chain(),
+            )
+            .add_event::<ResetTerrainEvent>()
+            .add_event::<TileEditEvent>();
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_terrain_reset_event(
     mut commands: Commands,
     mut reader: EventReader<ResetTerrainEvent>,
-    mut ev_writer: EventWriter<PlayerChunkUpdateEvent>,
-    player_pos: Res<CurrentPlayerChunkPosition>,
     mut chunks: ResMut<CurrentChunks>,
-    mut ground_tiles: ResMut<GroundTiles>,
+    mut pending_chunks: ResMut<PendingChunks>,
+    mut chunk_tasks: ResMut<ChunkTasks>,
+    mut world_tiles: ResMut<WorldTiles>,
+    mut fertility: ResMut<Fertility>,
+    mut deltas: ResMut<ChunkDeltas>,
+    mut rivers: ResMut<Rivers>,
     mut seed: ResMut<GenerationSeed>,
     tile_q: Query<Entity, With<TileComponent>>,
+    settlement_q: Query<Entity, With<Settlement>>,
 ) {
-    if reader.is_empty() {
+    let Some(reset) = reader.read().last() else {
         return;
-    }
+    };
 
-    reader.clear();
-    for t in tile_q.iter() {
+    for t in tile_q.iter().chain(settlement_q.iter()) {
         commands.entity(t).despawn();
     }
 
-    // Reset res
+    // Reset res, dropping a task cancels it
     chunks.0.clear();
-    ground_tiles.0.clear();
+    pending_chunks.0.clear();
+    chunk_tasks.0.clear();
+    world_tiles.0.clear();
+    fertility.0.clear();
+    *rivers = Rivers::default();
+
+    // A random seed is a brand new world, a given seed keeps the deltas loaded with it
+    if reset.0.is_none() {
+        deltas.0.clear();
+    }
 
     let mut rng = rand::rng();
-    seed.0 = rng.random();
+    seed.0 = reset.0.unwrap_or_else(|| rng.random());
+}
 
-    // Trigger world re-generation
-    let (x, y) = player_pos.0;
-    ev_writer.write(PlayerChunkUpdateEvent((x, y)));
+fn regenerate_on_settings_change(
+    settings: Res<WorldGenSettings>,
+    seed: Res<GenerationSeed>,
+    mut writer: EventWriter<ResetTerrainEvent>,
+) {
+    if settings.is_changed() && !settings.is_added() {
+        writer.write(ResetTerrainEvent(Some(seed.0)));
+    }
 }
 
-fn clean_ground_tiles(
+impl ChunkRect {
+    pub fn around(center: ChunkPos, radius: i32) -> Self {
+        Self {
+            min: center.offset(-radius, -radius),
+            max: center.offset(radius, radius),
+        }
+    }
+
+    /// Chunks covering a rectangle of world space
+    pub fn covering(area: Rect) -> Self {
+        Self {
+            min: WorldPos::new(area.min.x, area.max.y).to_chunk(),
+            max: WorldPos::new(area.max.x, area.min.y).to_chunk(),
+        }
+    }
+
+    pub fn grow(self, by: i32) -> Self {
+        Self {
+            min: self.min.offset(-by, -by),
+            max: self.max.offset(by, by),
+        }
+    }
+
+    pub fn contains(&self, chunk: ChunkPos) -> bool {
+        (self.min.x..=self.max.x).contains(&chunk.x) && (self.min.y..=self.max.y).contains(&chunk.y)
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = ChunkPos> {
+        (self.min.y..=self.max.y)
+            .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| ChunkPos::new(x, y)))
+    }
+}
+
+impl ViewArea {
+    pub fn wants(&self, chunk: ChunkPos) -> bool {
+        self.within(chunk, 0)
+    }
+
+    /// Loaded chunks are kept a little further out than new ones are loaded
+    pub fn keeps(&self, chunk: ChunkPos) -> bool {
+        self.within(chunk, CHUNK_UNLOAD_MARGIN)
+    }
+
+    fn within(&self, chunk: ChunkPos, margin: i32) -> bool {
+        self.bounds.contains_chunk(chunk)
+            && (ChunkRect::around(self.player, 1 + margin).contains(chunk)
+                || self.visible.is_some_and(|v| v.grow(margin).contains(chunk)))
+    }
+
+    /// Chunks to load, the ground under the player first, then the screen from the middle out
+    pub fn chunks(&self) -> Vec<ChunkPos> {
+        let player = ChunkRect::around(self.player, 1);
+        let mut chunks: Vec<_> = player
+            .iter()
+            .chain(self.visible.iter().flat_map(|v| v.iter()))
+            .collect();
+        let center = self.visible.map_or(self.player, |v| {
+            ChunkPos::new(
+                (v.min.x + v.max.x).div_euclid(2),
+                (v.min.y + v.max.y).div_euclid(2),
+            )
+        });
+        let distance = |c: ChunkPos, to: ChunkPos| c.x.abs_diff(to.x) + c.y.abs_diff(to.y);
+        chunks.sort_by_key(|c| {
+            (
+                !player.contains(*c),
+                distance(*c, self.player),
+                distance(*c, center),
+                *c,
+            )
+        });
+        chunks.dedup();
+        chunks.retain(|c| self.bounds.contains_chunk(*c));
+        chunks
+    }
+}
+
+fn update_view_area(
     player_pos: Res<CurrentPlayerChunkPosition>,
-    mut ground_tiles: ResMut<GroundTiles>,
+    settings: Res<WorldGenSettings>,
+    camera_q: Query<(&GlobalTransform, &Projection), With<Camera2d>>,
+    mut area: ResMut<ViewArea>,
 ) {
-    let (x, y) = player_pos.0;
-    ground_tiles.0.retain(|pos| {
-        let (px, py) = grid_to_chunk(pos.0 as f32, pos.1 as f32);
-        px.abs_diff(x) <= 1 || py.abs_diff(y) <= 1
+    let visible = camera_q.iter().find_map(|(transform, projection)| {
+        let Projection::Orthographic(ortho) = projection else {
+            return None;
+        };
+        let center = transform.translation().truncate();
+        Some(ChunkRect::covering(Rect::from_corners(
+            ortho.area.min + center,
+            ortho.area.max + center,
+        )))
+    });
+
+    area.set_if_neq(ViewArea {
+        player: player_pos.0,
+        visible,
+        bounds: settings.bounds,
     });
 }
 
 fn despawn_chunks(
     mut commands: Commands,
     mut current_chunks: ResMut<CurrentChunks>,
-    player_pos: Res<CurrentPlayerChunkPosition>,
+    mut pending_chunks: ResMut<PendingChunks>,
+    mut chunk_tasks: ResMut<ChunkTasks>,
+    mut world_tiles: ResMut<WorldTiles>,
+    mut fertility: ResMut<Fertility>,
+    area: Res<ViewArea>,
 ) {
     let mut keys_to_remove = Vec::new();
-    let (x, y) = player_pos.0;
 
-    for ((cx, cy), entities) in current_chunks.0.iter() {
-        if cx.abs_diff(x) <= 1 && cy.abs_diff(y) <= 1 {
+    pending_chunks.0.retain(|chunk| area.keeps(*chunk));
+    chunk_tasks.0.retain(|chunk, _| area.keeps(*chunk));
+
+    for (chunk, entities) in current_chunks.0.iter() {
+        if area.keeps(*chunk) {
             continue;
         }
 
         for e in entities.iter() {
             commands.entity(*e).despawn();
         }
-        keys_to_remove.push((*cx, *cy));
+        keys_to_remove.push(*chunk);
     }
 
-    for (cx, cy) in keys_to_remove {
-        current_chunks.0.remove(&(cx, cy));
+    for chunk in keys_to_remove {
+        current_chunks.0.remove(&chunk);
+        world_tiles.0.remove(&chunk);
+        fertility.0.remove(&chunk);
     }
 }
 
-fn handle_player_chunk_update_event(
+fn queue_chunks_in_view(
+    area: Res<ViewArea>,
+    current_chunks: Res<CurrentChunks>,
+    chunk_tasks: Res<ChunkTasks>,
+    mut pending_chunks: ResMut<PendingChunks>,
+) {
+    for chunk in area.chunks() {
+        if current_chunks.0.contains_key(&chunk)
+            || chunk_tasks.0.contains_key(&chunk)
+            || pending_chunks.0.contains(&chunk)
+        {
+            continue;
+        }
+
+        pending_chunks.0.push_back(chunk);
+    }
+}
+
+fn setup_chunk_material(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    atlas: Res<SpriteAtlas>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let material = materials.add(ColorMaterial::from(atlas.image.clone()));
+    commands.insert_resource(ChunkMaterial(material));
+}
+
+fn handle_tile_edit_event(
+    mut reader: EventReader<TileEditEvent>,
+    mut deltas: ResMut<ChunkDeltas>,
+    current_chunks: Res<CurrentChunks>,
+    mut pending_chunks: ResMut<PendingChunks>,
+    mut chunk_tasks: ResMut<ChunkTasks>,
+) {
+    for edit in reader.read() {
+        deltas.record(edit);
+
+        // Neighbours whose apron holds the tile redraw their shores too
+        let mut chunks: Vec<ChunkPos> = Neighbours::OFFSETS
+            .iter()
+            .chain(&[(0, 0)])
+            .map(|(i, j)| edit.pos.offset(*i, *j).to_chunk())
+            .collect();
+        chunks.sort();
+        chunks.dedup();
+
+        for chunk in chunks {
+            // Chunks that aren't loaded pick the delta up once they are
+            if !current_chunks.0.contains_key(&chunk) && !chunk_tasks.0.contains_key(&chunk) {
+                continue;
+            }
+
+            // A running task was started without this edit
+            chunk_tasks.0.remove(&chunk);
+            if !pending_chunks.0.contains(&chunk) {
+                pending_chunks.0.push_front(chunk);
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_chunk_tasks(
     seed: Res<GenerationSeed>,
-    mut current_chunks: ResMut<CurrentChunks>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    mut chunk_update_ev: EventReader<PlayerChunkUpdateEvent>,
-    mut ground_tiles: ResMut<GroundTiles>,
+    settings: Res<WorldGenSettings>,
+    deltas: Res<ChunkDeltas>,
+    atlas: Res<SpriteAtlas>,
+    biomes: Res<Biomes>,
+    rivers: Res<Rivers>,
+    mut pending_chunks: ResMut<PendingChunks>,
+    mut chunk_tasks: ResMut<ChunkTasks>,
 ) {
-    if chunk_update_ev.is_empty() {
+    let (Some(uvs), Some(sprites)) = (atlas.uvs.clone(), atlas.sprites.clone()) else {
+        return;
+    };
+    let Some(biomes) = biomes.registry.clone() else {
         return;
+    };
+
+    let pool = AsyncComputeTaskPool::get();
+    while chunk_tasks.0.len() < MAX_CHUNK_TASKS {
+        let Some(chunk) = pending_chunks.0.pop_front() else {
+            break;
+        };
+
+        let gen_seed = seed.0;
+        let uvs = uvs.clone();
+        let sprites = sprites.clone();
+        let biomes = biomes.clone();
+        let settings = settings.clone();
+        let deltas = deltas.around(chunk);
+        let rivers = rivers.0.clone();
+        let task = pool.spawn(async move {
+            let generated =
+                generate_edited_chunk(gen_seed, chunk, &settings, &biomes, &deltas, &rivers);
+            let tiles = chunk_sprites(&generated.data, &sprites);
+            let layers = build_chunk_meshes(chunk, &tiles, &uvs);
+            ChunkTaskResult { generated, layers }
+        });
+        chunk_tasks.0.insert(chunk, task);
     }
+}
 
-    let texture_handle: Handle<Image> = asset_server.load(SPRITE_SHEET_PATH);
-    let texture_atlas = TextureAtlasLayout::from_grid(
-        uvec2(TILE_W as u32, TILE_H as u32),
-        SPRITE_SHEET_W as u32,
-        SPRITE_SHEET_H as u32,
-        Some(UVec2::splat(SPRITE_PADDING)),
-        Some(UVec2::splat(SPRITE_SHEET_OFFSET)),
-    );
-    let handle = texture_atlases.add(texture_atlas);
-
-    for new_chunk_pos in chunk_update_ev.read() {
-        let (x, y) = new_chunk_pos.0;
-
-        let chunk_neighbors = [
-            (-1, 0),
-            (1, 0),
-            (0, -1),
-            (0, 1),
-            (-1, 1),
-            (1, 1),
-            (-1, -1),
-            (1, -1),
-            (0, 0),
-        ];
-        let mut tiles = HashSet::new();
-        let mut ground_map = HashSet::new();
-
-        for (i, j) in chunk_neighbors.iter() {
-            let (x, y) = (x + *i, y + *j);
-            if current_chunks.0.contains_key(&(x, y)) {
+#[allow(clippy::too_many_arguments)]
+fn spawn_generated_chunks(
+    mut commands: Commands,
+    material: Res<ChunkMaterial>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut current_chunks: ResMut<CurrentChunks>,
+    mut chunk_tasks: ResMut<ChunkTasks>,
+    mut world_tiles: ResMut<WorldTiles>,
+    mut fertility_map: ResMut<Fertility>,
+    settlement_q: Query<&Settlement>,
+) {
+    let mut finished = Vec::new();
+    for (chunk, task) in chunk_tasks.0.iter_mut() {
+        if finished.len() >= CHUNKS_SPAWNED_PER_FRAME {
+            break;
+        }
+        if let Some(generated) = check_ready(task) {
+            finished.push((*chunk, generated));
+        }
+    }
+
+    for (chunk, ChunkTaskResult { generated, layers }) in finished {
+        chunk_tasks.0.remove(&chunk);
+        world_tiles.0.insert(chunk, generated.data);
+        fertility_map.0.insert(chunk, generated.fertility);
+
+        // Settlements outlive their chunk, they keep trading while the player is away
+        for settlement in generated.settlements {
+            if settlement_q.iter().any(|s| s.center == settlement.center) {
                 continue;
             }
-
-            let start = (x * CHUNK_W as i32, y * CHUNK_H as i32);
-            let (chunk_tiles, chunk_ground_map) = gen_chunk(seed.0, (start.0, start.1));
-            tiles.extend(chunk_tiles);
-            ground_map.extend(chunk_ground_map);
+            let translation = Vec2::from(settlement.center.to_world()).extend(0.0);
+            commands.spawn((
+                Name::new(settlement.name.clone()),
+                settlement,
+                Transform::from_translation(translation),
+            ));
         }
 
-        let mut updated_ground_map = HashSet::new();
-        for (x, y) in ground_map.iter() {
-            let (num_nei, tile) = process_tile((*x, *y), &ground_map);
-            if num_nei == 1 {
-                continue;
+        // Edited chunks are regenerated while still loaded
+        if let Some(old) = current_chunks.0.remove(&chunk) {
+            for e in old {
+                commands.entity(e).despawn();
             }
+        }
+
+        let origin = chunk_origin(chunk);
+        let entities = layers
+            .into_iter()
+            .map(|layer| {
+                commands
+                    .spawn((
+                        Mesh2d(meshes.add(layer.mesh)),
+                        MeshMaterial2d(material.0.clone()),
+                        Transform::from_translation(origin.extend(layer.z_index as f32)),
+                        TileComponent,
+                    ))
+                    .id()
+            })
+            .collect();
+        current_chunks.0.insert(chunk, entities);
+    }
+}
 
-            // Ignore edges
-            // This will help in better player visualization when going from land to water
-            updated_ground_map.insert((*x, *y));
-            tiles.insert(Tile::new((*x, *y), tile, 0));
+/// Every random decision made while generating a chunk comes from this rng,
+/// so the same seed and chunk always produce the same tiles.
+pub fn chunk_rng(gen_seed: u32, chunk: ChunkPos) -> ChaCha8Rng {
+    let mut rng_seed = [0; 32];
+    rng_seed[0..4].copy_from_slice(&gen_seed.to_le_bytes());
+    rng_seed[4..8].copy_from_slice(&chunk.x.to_le_bytes());
+    rng_seed[8..12].copy_from_slice(&chunk.y.to_le_bytes());
+    ChaCha8Rng::from_seed(rng_seed)
+}
+
+impl ClimateNoise {
+    fn new(gen_seed: u32, settings: &WorldGenSettings) -> Self {
+        Self {
+            elevation: settings.build_elevation(gen_seed),
+            patches: Perlin::new(gen_seed),
+            minerals: Perlin::new(gen_seed.wrapping_add(3)),
+            soil: Perlin::new(gen_seed.wrapping_add(4)),
+            temperature: Perlin::new(gen_seed.wrapping_add(1)),
+            moisture: Perlin::new(gen_seed.wrapping_add(2)),
         }
-        ground_tiles.0.extend(updated_ground_map);
-
-        for t in tiles.iter() {
-            let (cx, cy) = grid_to_chunk(t.pos.0 as f32, t.pos.1 as f32);
-            let (x, y) = grid_to_world(t.pos.0 as f32, t.pos.1 as f32);
-            let (x, y) = center_to_top_left(x, y);
-
-            let e = commands
-                .spawn((
-                    Sprite::from_atlas_image(
-                        texture_handle.clone(),
-                        TextureAtlas {
-                            layout: handle.clone(),
-                            index: t.sprite,
-                        },
-                    ),
-                    Transform::from_scale(Vec3::splat(SPRITE_SCALE_FACTOR as f32))
-                        .with_translation(vec3(x, y, t.z_index as f32)),
-                    TileComponent,
-                ))
-                .id();
-
-            current_chunks
-                .0
-                .entry((cx, cy))
-                .or_insert_with(Vec::new)
-                .push(e);
+    }
+
+    fn sample(&self, x: i32, y: i32) -> Climate {
+        self.sample_above(x, y, self.elevation.get([x as f64, y as f64]))
+    }
+
+    /// The rest of the climate of a tile whose elevation is already known
+    fn sample_above(&self, x: i32, y: i32, elevation: f64) -> Climate {
+        let (x, y) = (x as f64, y as f64);
+
+        Climate {
+            elevation,
+            // Colder up in the hills
+            temperature: self.temperature.get([x / 300.5, y / 300.5]) - elevation.max(0.0) * 0.5,
+            moisture: self.moisture.get([x / 220.5, y / 220.5]),
+            patches: self.patches.get([x / 43.5, y / 43.5]),
+            minerals: self.minerals.get([x / 31.5, y / 31.5]),
+            soil: self.soil.get([x / 57.5, y / 57.5]),
         }
     }
 }
 
-fn gen_chunk(gen_seed: u32, start: (i32, i32)) -> (HashSet<Tile>, HashSet<(i32, i32)>) {
-    let mut rng = rand::rng();
-    let noise = Perlin::new(gen_seed);
-
-    let mut tiles = HashSet::new();
-    let mut ground_map = HashSet::new();
-    let end = (start.0 + CHUNK_W as i32, start.1 + CHUNK_H as i32);
-    for x in start.0 - 1..end.0 + 1 {
-        for y in start.1 - 1..end.1 + 1 {
-            let noise_val1 = noise.get([x as f64 / 100.5, y as f64 / 100.5]);
-            let noise_val2 = noise.get([x as f64 / 53.5, y as f64 / 53.5]);
-            let noise_val3 = noise.get([x as f64 / 43.5, y as f64 / 43.5]);
-            let noise_val4 = noise.get([x as f64 / 23.5, y as f64 / 23.5]);
-            let noise_val = (noise_val1 + noise_val2 + noise_val3 + noise_val4) / 4.0;
-            let chance = rng.random_range(0.0..1.0);
+pub fn gen_chunk(
+    gen_seed: u32,
+    chunk: ChunkPos,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+) -> ChunkData {
+    generate_chunk(gen_seed, chunk, settings, biomes).data
+}
 
-            // Ground
-            if noise_val > 0.0 {
-                ground_map.insert((x, y));
-            } else {
+pub fn generate_chunk(
+    gen_seed: u32,
+    chunk: ChunkPos,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+) -> GeneratedChunk {
+    generate_edited_chunk(
+        gen_seed,
+        chunk,
+        settings,
+        biomes,
+        &[],
+        &RiverCache::default(),
+    )
+}
+
+/// The chunk with the player's `deltas` applied, fertility takes the edited
+/// tiles into account too. `rivers` must only hold rivers of this seed and settings.
+pub fn generate_edited_chunk(
+    gen_seed: u32,
+    chunk: ChunkPos,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+    deltas: &[ChunkDelta],
+    rivers: &RiverCache,
+) -> GeneratedChunk {
+    let mut rng = chunk_rng(gen_seed, chunk);
+    let noise = ClimateNoise::new(gen_seed, settings);
+    let mut data = ChunkData::new(chunk);
+
+    // Sampled a tile further out than water still makes the chunk's soil
+    // fertile, so every tile in that reach can be classified
+    let apron = FERTILITY_WATER_REACH + 1;
+    let start = chunk.origin();
+    let end = start.offset(CHUNK_W as i32, CHUNK_H as i32);
+    let samples_w = CHUNK_W as i32 + 2 * apron;
+    let samples: Vec<Climate> = (start.y - apron..end.y + apron)
+        .flat_map(|y| (start.x - apron..end.x + apron).map(move |x| (x, y)))
+        .map(|(x, y)| noise.sample(x, y))
+        .collect();
+    let sample_at = |x: i32, y: i32| {
+        &samples[((y - start.y + apron) * samples_w + x - start.x + apron) as usize]
+    };
+    let sampled = |p: GridPos| {
+        ((start.x - apron..end.x + apron).contains(&p.x)
+            && (start.y - apron..end.y + apron).contains(&p.y))
+        .then(|| sample_at(p.x, p.y))
+    };
+
+    // Roads passing through the chunk are searched for in full, over all the
+    // ground between the villages they join
+    let roads = roads_near(
+        gen_seed,
+        &noise.elevation,
+        rivers,
+        settings,
+        start.offset(-1, -1),
+        end.offset(1, 1),
+    );
+    let (min, max) = roads.iter().fold(
+        (start.offset(-apron, -apron), end.offset(apron, apron)),
+        |(min, max), road| {
+            (
+                GridPos::new(min.x.min(road.min.x), min.y.min(road.min.y)),
+                GridPos::new(max.x.max(road.max.x), max.y.max(road.max.y)),
+            )
+        },
+    );
+    let fresh_water = rivers.fresh_water(gen_seed, &noise.elevation, &settings.rivers, min, max);
+    let is_land = |p: GridPos| sample_at(p.x, p.y).elevation > 0.0 && !fresh_water.contains_key(&p);
+    let water_at = |p: GridPos| fresh_water.get(&p).copied().unwrap_or(TerrainKind::Ocean);
+
+    for x in start.x - 1..end.x + 1 {
+        for y in start.y - 1..end.y + 1 {
+            let terrain = classify_ground(GridPos::new(x, y), is_land, water_at);
+            let biome = biomes.biome_at(sample_at(x, y)) as u8;
+            data.set(GridPos::new(x, y), TileData::new(terrain).with_biome(biome));
+        }
+    }
+
+    for x in start.x..end.x {
+        for y in start.y..end.y {
+            let chance = rng.random_range(0.0..1.0);
+            let pos = GridPos::new(x, y);
+            let Some(tile) = data.get_mut(pos) else {
                 continue;
+            };
+            if tile.terrain.is_fresh_water() {
+                tile.variant = rng.random();
             }
-
+            let climate = sample_at(x, y);
             // Too close to shore
-            if noise_val < 0.05 {
+            if !tile.terrain.is_land() || climate.elevation < 0.05 {
                 continue;
             }
 
-            // Dense Forest
-            if (noise_val > 0.5 || noise_val3 > 0.98) && chance > 0.2 {
-                tiles.insert(Tile::new((x, y), 27, 5));
-                continue;
+            let biome = &biomes.biomes[tile.biome as usize];
+            if let Some(feature) = biome.pick_feature(climate, chance) {
+                *tile = tile.with_feature(feature, rng.random());
             }
-            // Patch Forest
-            if noise_val3 > 0.5 && noise_val < 0.5 && chance > 0.4 {
-                let chance2 = rng.random_range(0.0..1.0);
-                let tile = if chance2 > 0.7 {
-                    rng.random_range(24..=26)
-                } else {
-                    rng.random_range(24..=25)
-                };
-                tiles.insert(Tile::new((x, y), tile, 3));
-                continue;
-            }
-            // Sparse Forest
-            if noise_val4 > 0.4 && noise_val < 0.5 && noise_val3 < 0.5 && chance > 0.9 {
-                let chance = rng.random_range(0.0..1.0);
-                let tile = if chance > 0.78 {
-                    rng.random_range(28..=29)
-                } else {
-                    rng.random_range(24..=25)
-                };
-                tiles.insert(Tile::new((x, y), tile, 3));
-                continue;
+            // Some deposits are richer than others
+            if let Some(Feature::Deposit(_)) = tile.feature {
+                tile.quantity = (tile.quantity as f64 * rng.random_range(0.5..1.5)) as u32;
             }
+        }
+    }
 
-            // Settlements
-            if noise_val > 0.1 && noise_val < 0.3 && noise_val3 < 0.4 && chance > 0.8 {
-                let chance2 = rng.random_range(0.0..1.0);
-
-                if chance2 > 0.98 {
-                    let chance3 = rng.random_range(0.0..1.0);
-                    let tile = if chance3 > 0.75 {
-                        rng.random_range(18..=19)
-                    } else {
-                        rng.random_range(16..=17)
-                    };
-                    tiles.insert(Tile::new((x, y), tile, 8));
-                } else {
-                    if noise_val > 0.2 && noise_val < 0.3 && noise_val3 < 0.3 && chance > 0.9 {
-                        tiles.insert(Tile::new((x, y), 32, 1));
-                    }
-                }
-
-                continue;
+    // Villages are built over whatever grew there
+    let villages = villages_near(gen_seed, &noise.elevation, rivers, settings, min, max);
+    for village in villages.iter() {
+        for (pos, (feature, variant)) in village.tiles.iter() {
+            if let Some(tile) = data.get_mut(*pos).filter(|t| t.terrain.is_land()) {
+                *tile = tile.with_feature(*feature, *variant);
             }
+        }
+    }
 
-            // Color Check
-            // if noise_val > 0.1 && noise_val4 < 0.5 {
-            //     tiles.insert(Tile::new((x, y), 64, 1));
-            //     continue;
-            // }
+    let mut road_cost = HashMap::new();
+    let mut cost = |p: GridPos| {
+        *road_cost
+            .entry(p)
+            .or_insert_with(|| road_cost_at(p, sampled(p), &noise, &fresh_water, &villages, biomes))
+    };
+    let streets = villages.iter().flat_map(|v| v.streets.iter().copied());
+    let paths = roads.iter().filter_map(|road| find_road(road, &mut cost));
+    for pos in streets.chain(paths.flatten()) {
+        let Some(tile) = data.at_mut(pos) else {
+            continue;
+        };
+        // Roads lead through villages, not over them
+        if !matches!(
+            tile.feature,
+            Some(Feature::House(_) | Feature::Market | Feature::SettlementGround)
+        ) {
+            tile.feature = None;
+            tile.quantity = 0;
         }
+        tile.road = true;
+    }
+    let settlements = villages
+        .into_iter()
+        .map(|v| v.settlement)
+        .filter(|s| data.contains(s.center))
+        .collect();
+
+    for delta in deltas {
+        delta.apply(&mut data);
     }
+    // Edits further out than the apron still bring water closer or take it away
+    let is_ground = |p: GridPos| match deltas.iter().find_map(|d| d.0.get(&p)) {
+        Some(tile) => tile.terrain.is_land(),
+        None => classify_ground(p, is_land, water_at).is_land(),
+    };
+    let fertility = fertility_layer(chunk, is_ground, |p| {
+        let climate = sample_at(p.x, p.y);
+        let biome = &biomes.biomes[biomes.biome_at(climate)];
+        biome.fertility * 0.7 + (climate.soil + 1.0) * 0.15
+    });
 
-    (tiles, ground_map)
+    GeneratedChunk {
+        data,
+        fertility,
+        settlements,
+    }
 }
 
-fn process_tile((x, y): (i32, i32), occupied: &HashSet<(i32, i32)>) -> (i32, usize) {
-    let nei_options = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    let mut nei = [1, 1, 1, 1];
-    let mut nei_count = 4;
-    for (idx, (i, j)) in nei_options.iter().enumerate() {
-        if !occupied.contains(&(x + i, y + j)) {
-            nei[idx] = 0;
-            nei_count -= 1;
+/// Price of building a road over a tile, `None` where it can't go. Roads follow
+/// village streets, bridge rivers reluctantly and keep out of forests and mountains.
+/// Tiles without a `sampled` climate only sample what their cost comes down to.
+fn road_cost_at(
+    pos: GridPos,
+    sampled: Option<&Climate>,
+    noise: &ClimateNoise,
+    fresh_water: &HashMap<GridPos, TerrainKind>,
+    villages: &[Village],
+    biomes: &BiomeRegistry,
+) -> Option<u32> {
+    for village in villages.iter() {
+        if village.streets.contains(&pos) {
+            return Some(1);
+        }
+        match village.tiles.get(&pos) {
+            Some((Feature::SettlementGround | Feature::Market, _)) => return Some(1),
+            Some((Feature::Field, _)) => return Some(8),
+            Some(_) => return None,
+            None => {}
         }
     }
 
-    let tile = match nei {
-        [0, 1, 1, 0] => 3,
-        [1, 0, 1, 0] => 4,
-        [0, 1, 0, 1] => 1,
-        [1, 0, 0, 1] => 2,
-        _ => 0,
-    };
+    let elevation = sampled.map_or_else(
+        || noise.elevation.get([pos.x as f64, pos.y as f64]),
+        |c| c.elevation,
+    );
+    if elevation <= 0.0 {
+        return None;
+    }
+    if fresh_water.contains_key(&pos) {
+        return Some(12);
+    }
+    let climate = sampled
+        .copied()
+        .unwrap_or_else(|| noise.sample_above(pos.x, pos.y, elevation));
+    let biome = &biomes.biomes[biomes.biome_at(&climate)];
+    let rough = biome.chance_of(&climate, |f| {
+        matches!(f, Feature::DenseForest | Feature::Mountain(_))
+    });
+    Some(3 + (rough.min(1.0) * 20.0) as u32)
+}
+
+fn classify_ground(
+    pos: GridPos,
+    is_land: impl Fn(GridPos) -> bool,
+    water_at: impl Fn(GridPos) -> TerrainKind,
+) -> TerrainKind {
+    if !is_land(pos) {
+        return water_at(pos);
+    }
 
-    (nei_count, tile)
+    let land = Neighbours::of(pos, &is_land);
+    let sides = [autotile::N, autotile::E, autotile::S, autotile::W];
+    match sides.iter().filter(|side| land.has(**side)).count() {
+        // Ignore edges, they become the water they poke into
+        // This will help in better player visualization when going from land to water
+        1 => [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .into_iter()
+            .map(|(i, j)| pos.offset(i, j))
+            .find(|p| !is_land(*p))
+            .map_or(TerrainKind::Ocean, water_at),
+        _ if land.0 == u8::MAX => TerrainKind::Grass,
+        _ => TerrainKind::Shore,
+    }
+}
+
+/// Sprites of every tile in the chunk, derived from its tile data
+pub fn chunk_sprites(data: &ChunkData, sprites: &SpriteRegistry) -> Vec<TileSprite> {
+    let mut tiles = Vec::new();
+    for (pos, tile) in data.iter() {
+        if tile.terrain.is_land() {
+            let land = Neighbours::of(pos, |p| data.terrain_at(p).is_land());
+            tiles.push(TileSprite::new(pos, shore_sprite(land, sprites), 0));
+        }
+        // Over fresh water it's a bridge, the plaza is paved already
+        if tile.road && tile.feature != Some(Feature::SettlementGround) {
+            let roads = Neighbours::of(pos, |p| data.road_at(p));
+            tiles.push(TileSprite::new(pos, road_sprite(roads, sprites), 1));
+        }
+        // The sea is the background colour, fresh water is drawn
+        if tile.terrain.is_fresh_water() {
+            let sprite = sprites.get("water.fresh").frame(tile.variant as usize);
+            tiles.push(TileSprite::new(pos, sprite, 0));
+        }
+        if let Some(feature) = tile.feature {
+            let sprite = sprites
+                .get(feature.sprite_name())
+                .frame(tile.variant as usize);
+            tiles.push(TileSprite::new(pos, sprite, feature.z_index()));
+        }
+    }
+    tiles
 }
 
-impl Tile {
-    fn new(pos: (i32, i32), sprite: usize, z_index: i32) -> Self {
+impl TileSprite {
+    fn new(pos: GridPos, sprite: usize, z_index: i32) -> Self {
         Self {
             pos,
             sprite,
@@ -0,0 +1,280 @@
+use crate::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Tiles of every loaded chunk, the model game logic queries and sprites are derived from
+#[derive(Resource, Default)]
+pub struct WorldTiles(pub HashMap<ChunkPos, ChunkData>);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TerrainKind {
+    #[default]
+    #[serde(alias = "Water")]
+    Ocean,
+    River,
+    Lake,
+    Shore,
+    Grass,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Tree(TreeSpecies),
+    DenseForest,
+    House(HouseSize),
+    SettlementGround,
+    Market,
+    Field,
+    Mountain(MountainSize),
+    Rocks,
+    Cactus,
+    Reeds,
+    Deposit(Mineral),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TreeSpecies {
+    Pine,
+    Oak,
+    Dead,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HouseSize {
+    Small,
+    Large,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mineral {
+    Stone,
+    Iron,
+    Coal,
+    Gold,
+    Clay,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MountainSize {
+    Small,
+    Large,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileData {
+    pub terrain: TerrainKind,
+    pub feature: Option<Feature>,
+    /// Index into the `BiomeRegistry` the tile was generated with
+    pub biome: u8,
+    /// Picks one of the sprite variants of the feature
+    pub variant: u8,
+    /// Resources left to gather from the feature, like wood in a tree
+    pub quantity: u32,
+    #[serde(default)]
+    pub road: bool,
+}
+
+/// Tiles of one chunk plus a one tile apron copied from its neighbours,
+/// so shores on the chunk edge can be resolved without them
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkData {
+    pub pos: ChunkPos,
+    tiles: Vec<TileData>,
+}
+
+impl TerrainKind {
+    pub fn is_land(&self) -> bool {
+        matches!(self, TerrainKind::Shore | TerrainKind::Grass)
+    }
+
+    pub fn is_fresh_water(&self) -> bool {
+        matches!(self, TerrainKind::River | TerrainKind::Lake)
+    }
+}
+
+impl Feature {
+    /// Every feature there is, new ones need adding here for their sprite to be required
+    pub const ALL: [Feature; 19] = [
+        Feature::Tree(TreeSpecies::Pine),
+        Feature::Tree(TreeSpecies::Oak),
+        Feature::Tree(TreeSpecies::Dead),
+        Feature::DenseForest,
+        Feature::House(HouseSize::Small),
+        Feature::House(HouseSize::Large),
+        Feature::SettlementGround,
+        Feature::Market,
+        Feature::Field,
+        Feature::Mountain(MountainSize::Small),
+        Feature::Mountain(MountainSize::Large),
+        Feature::Rocks,
+        Feature::Cactus,
+        Feature::Reeds,
+        Feature::Deposit(Mineral::Stone),
+        Feature::Deposit(Mineral::Iron),
+        Feature::Deposit(Mineral::Coal),
+        Feature::Deposit(Mineral::Gold),
+        Feature::Deposit(Mineral::Clay),
+    ];
+
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            Feature::Tree(TreeSpecies::Pine) => "tree.pine",
+            Feature::Tree(TreeSpecies::Oak) => "tree.oak",
+            Feature::Tree(TreeSpecies::Dead) => "tree.dead",
+            Feature::DenseForest => "forest.dense",
+            Feature::House(HouseSize::Small) => "house.small",
+            Feature::House(HouseSize::Large) => "house.large",
+            Feature::SettlementGround => "settlement.ground",
+            Feature::Market => "settlement.market",
+            Feature::Field => "field",
+            Feature::Mountain(MountainSize::Small) => "mountain.small",
+            Feature::Mountain(MountainSize::Large) => "mountain.large",
+            Feature::Rocks => "rocks",
+            Feature::Cactus => "cactus",
+            Feature::Reeds => "reeds",
+            Feature::Deposit(Mineral::Stone) => "deposit.stone",
+            Feature::Deposit(Mineral::Iron) => "deposit.iron",
+            Feature::Deposit(Mineral::Coal) => "deposit.coal",
+            Feature::Deposit(Mineral::Gold) => "deposit.gold",
+            Feature::Deposit(Mineral::Clay) => "deposit.clay",
+        }
+    }
+
+    pub fn z_index(&self) -> i32 {
+        match self {
+            Feature::SettlementGround | Feature::Field => 1,
+            Feature::Rocks | Feature::Deposit(_) => 2,
+            Feature::Tree(_) | Feature::Cactus | Feature::Reeds => 3,
+            Feature::DenseForest => 5,
+            Feature::Mountain(_) => 6,
+            Feature::House(_) | Feature::Market => 8,
+        }
+    }
+
+    pub fn initial_quantity(&self) -> u32 {
+        match self {
+            Feature::Tree(TreeSpecies::Dead) => 4,
+            Feature::Tree(_) => 10,
+            Feature::DenseForest => 25,
+            Feature::Mountain(MountainSize::Small) => 30,
+            Feature::Mountain(MountainSize::Large) => 60,
+            Feature::Rocks => 8,
+            Feature::Cactus => 3,
+            Feature::Reeds => 5,
+            Feature::Field => 20,
+            Feature::Deposit(Mineral::Stone) => 80,
+            Feature::Deposit(Mineral::Iron) => 40,
+            Feature::Deposit(Mineral::Coal) => 50,
+            Feature::Deposit(Mineral::Gold) => 15,
+            Feature::Deposit(Mineral::Clay) => 60,
+            Feature::House(_) | Feature::SettlementGround | Feature::Market => 0,
+        }
+    }
+}
+
+impl HouseSize {
+    pub fn residents(&self) -> u32 {
+        match self {
+            HouseSize::Small => 4,
+            HouseSize::Large => 8,
+        }
+    }
+}
+
+impl TileData {
+    pub fn new(terrain: TerrainKind) -> Self {
+        Self {
+            terrain,
+            ..default()
+        }
+    }
+
+    pub fn with_biome(mut self, biome: u8) -> Self {
+        self.biome = biome;
+        self
+    }
+
+    pub fn with_feature(mut self, feature: Feature, variant: u8) -> Self {
+        self.feature = Some(feature);
+        self.variant = variant;
+        self.quantity = feature.initial_quantity();
+        self
+    }
+}
+
+impl ChunkData {
+    /// All water until generated
+    pub fn new(pos: ChunkPos) -> Self {
+        Self {
+            pos,
+            tiles: vec![TileData::default(); (CHUNK_W + 2) * (CHUNK_H + 2)],
+        }
+    }
+
+    pub fn contains(&self, pos: GridPos) -> bool {
+        pos.to_chunk() == self.pos
+    }
+
+    /// Index of a tile in the chunk or its apron
+    fn index(&self, pos: GridPos) -> Option<usize> {
+        let origin = self.pos.origin();
+        let (lx, ly) = (pos.x - origin.x + 1, pos.y - origin.y + 1);
+        if lx < 0 || ly < 0 || lx >= CHUNK_W as i32 + 2 || ly >= CHUNK_H as i32 + 2 {
+            return None;
+        }
+        Some(ly as usize * (CHUNK_W + 2) + lx as usize)
+    }
+
+    pub fn get(&self, pos: GridPos) -> Option<&TileData> {
+        if !self.contains(pos) {
+            return None;
+        }
+        self.index(pos).map(|i| &self.tiles[i])
+    }
+
+    pub fn get_mut(&mut self, pos: GridPos) -> Option<&mut TileData> {
+        if !self.contains(pos) {
+            return None;
+        }
+        self.index(pos).map(|i| &mut self.tiles[i])
+    }
+
+    /// Also answers for the apron, anything further out is ocean
+    pub fn terrain_at(&self, pos: GridPos) -> TerrainKind {
+        self.index(pos)
+            .map_or(TerrainKind::Ocean, |i| self.tiles[i].terrain)
+    }
+
+    /// Tile of the chunk or its apron
+    pub fn at_mut(&mut self, pos: GridPos) -> Option<&mut TileData> {
+        self.index(pos).map(|i| &mut self.tiles[i])
+    }
+
+    /// Also answers for the apron
+    pub fn road_at(&self, pos: GridPos) -> bool {
+        self.index(pos).is_some_and(|i| self.tiles[i].road)
+    }
+
+    /// Sets a tile of the chunk or its apron
+    pub fn set(&mut self, pos: GridPos, tile: TileData) {
+        if let Some(i) = self.index(pos) {
+            self.tiles[i] = tile;
+        }
+    }
+
+    /// Tiles of the chunk, without the apron
+    pub fn iter(&self) -> impl Iterator<Item = (GridPos, &TileData)> {
+        (0..CHUNK_H as u32)
+            .flat_map(|y| (0..CHUNK_W as u32).map(move |x| LocalTilePos { x, y }))
+            .map(|local| self.pos.tile(local))
+            .map(|pos| (pos, &self.tiles[self.index(pos).unwrap()]))
+    }
+}
+
+impl WorldTiles {
+    /// `None` while the chunk holding the tile isn't loaded
+    pub fn tile_at(&self, pos: GridPos) -> Option<&TileData> {
+        self.0.get(&pos.to_chunk())?.get(pos)
+    }
+}
@@ -0,0 +1,80 @@
+use crate::*;
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    tasks::{ComputeTaskPool, TaskPool},
+};
+use biome::BiomeRegistry;
+use terrain::generate_chunk;
+use world::*;
+use worldgen::WorldGenSettings;
+
+/// Colour of a tile on the world map, one pixel per tile
+pub fn tile_color(tile: &TileData) -> [u8; 3] {
+    match tile.feature {
+        Some(Feature::Tree(_) | Feature::DenseForest) => return [0, 135, 81],
+        Some(Feature::Cactus | Feature::Reeds) => return [0, 181, 67],
+        Some(Feature::Mountain(_) | Feature::Rocks) => return [95, 88, 84],
+        Some(Feature::House(_) | Feature::Market | Feature::SettlementGround) => {
+            return [198, 80, 70]
+        }
+        Some(Feature::Field) => return [230, 180, 40],
+        Some(Feature::Deposit(Mineral::Stone)) => return [194, 195, 199],
+        Some(Feature::Deposit(Mineral::Iron)) => return [131, 118, 156],
+        Some(Feature::Deposit(Mineral::Coal)) => return [35, 34, 40],
+        Some(Feature::Deposit(Mineral::Gold)) => return [255, 204, 0],
+        Some(Feature::Deposit(Mineral::Clay)) => return [205, 140, 95],
+        None if tile.road => return [171, 82, 54],
+        None => {}
+    }
+
+    match tile.terrain {
+        TerrainKind::Ocean => [6, 90, 181],
+        TerrainKind::River | TerrainKind::Lake => [140, 190, 214],
+        TerrainKind::Shore => [230, 210, 160],
+        TerrainKind::Grass => [200, 220, 150],
+    }
+}
+
+/// Map of the `size` chunks starting at `from`, generated without deltas
+pub fn render_world_map(
+    gen_seed: u32,
+    from: ChunkPos,
+    size: UVec2,
+    settings: &WorldGenSettings,
+    biomes: &BiomeRegistry,
+) -> Image {
+    let chunks: Vec<ChunkPos> = (0..size.y as i32)
+        .flat_map(|y| (0..size.x as i32).map(move |x| from.offset(x, y)))
+        .collect();
+    let generated = ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+        for chunk in chunks {
+            scope.spawn(async move { generate_chunk(gen_seed, chunk, settings, biomes).data });
+        }
+    });
+
+    let (w, h) = (size.x as usize * CHUNK_W, size.y as usize * CHUNK_H);
+    let origin = from.origin();
+    let mut pixels = vec![0; w * h * 4];
+    for data in generated.iter() {
+        for (pos, tile) in data.iter() {
+            let (x, y) = ((pos.x - origin.x) as usize, (pos.y - origin.y) as usize);
+            let i = (y * w + x) * 4;
+            pixels[i..i + 3].copy_from_slice(&tile_color(tile));
+            pixels[i + 3] = 255;
+        }
+    }
+
+    Image::new(
+        Extent3d {
+            width: w as u32,
+            height: h as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        pixels,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
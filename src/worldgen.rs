@@ -0,0 +1,360 @@
+use crate::*;
+use bevy::prelude::*;
+use hydrology::RiverSettings;
+use noise::{
+    Constant, Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti, ScalePoint, Seedable, Turbulence,
+};
+use serde::{Deserialize, Serialize};
+use settlement::SettlementSettings;
+use std::str::FromStr;
+
+/// Elevation noise built from the settings, sampled with grid coordinates
+pub type Elevation = Box<dyn NoiseFn<f64, 2> + Send + Sync>;
+
+/// The sea rises over this many tiles towards the edge of a bounded world
+pub const WORLD_EDGE_FALLOFF: f64 = 120.0;
+/// How far the coast along the edge wanders in and out, so it isn't a rectangle
+pub const WORLD_EDGE_WOBBLE: f64 = 40.0;
+
+/// How the world is shaped, changing it regenerates the terrain
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorldGenSettings {
+    /// Land starts where this is above 0.0
+    pub elevation: NoiseNode,
+    #[serde(default)]
+    pub rivers: RiverSettings,
+    #[serde(default)]
+    pub settlements: SettlementSettings,
+    #[serde(default)]
+    pub bounds: WorldBounds,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldBounds {
+    #[default]
+    Infinite,
+    /// `GRID_COLS` x `GRID_ROWS` tiles around the spawn, surrounded by ocean
+    Grid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorldPreset {
+    #[default]
+    Classic,
+    Continent,
+    Archipelago,
+    InlandLakes,
+}
+
+/// Node of the elevation graph, scales are feature sizes in tiles and
+/// `seed` is added to the world seed so nodes don't repeat each other
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum NoiseNode {
+    Perlin {
+        scale: f64,
+        #[serde(default)]
+        seed: u32,
+    },
+    /// Octaves of perlin noise, each twice as fine and half as strong
+    Fbm {
+        scale: f64,
+        octaves: usize,
+        #[serde(default)]
+        seed: u32,
+    },
+    /// Sharp crests where the noise crosses zero, like mountain ranges
+    Ridged {
+        scale: f64,
+        octaves: usize,
+        #[serde(default)]
+        seed: u32,
+    },
+    /// Pushes the sampled position around by up to `strength` tiles,
+    /// breaking up the blobby look of plain noise
+    Warp {
+        source: Box<NoiseNode>,
+        scale: f64,
+        strength: f64,
+        #[serde(default)]
+        seed: u32,
+    },
+    /// 1.0 at the centre of the initial window, falling to -1.0 at `radius` tiles
+    Falloff {
+        radius: f64,
+    },
+    Constant(f64),
+    /// Weighted sum of the inputs
+    Sum(Vec<(f64, NoiseNode)>),
+}
+
+/// Radial mask around the centre of the initial window
+struct Falloff {
+    center: GridPos,
+    radius: f64,
+}
+
+struct WeightedSum(Vec<(f64, Elevation)>);
+
+/// Sinks the elevation into the sea towards the edge of the grid
+struct EdgeFalloff {
+    source: Elevation,
+    wobble: Perlin,
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> Self {
+        WorldPreset::Classic.settings()
+    }
+}
+
+impl WorldPreset {
+    pub const ALL: [WorldPreset; 4] = [
+        WorldPreset::Classic,
+        WorldPreset::Continent,
+        WorldPreset::Archipelago,
+        WorldPreset::InlandLakes,
+    ];
+
+    /// Name the preset is picked by on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            WorldPreset::Classic => "classic",
+            WorldPreset::Continent => "continent",
+            WorldPreset::Archipelago => "archipelago",
+            WorldPreset::InlandLakes => "inland-lakes",
+        }
+    }
+
+    pub fn settings(self) -> WorldGenSettings {
+        let perlin = |scale| (0.25, NoiseNode::Perlin { scale, seed: 0 });
+        let elevation = match self {
+            // The original four octaves, averaged
+            WorldPreset::Classic => NoiseNode::Sum(vec![
+                perlin(100.5),
+                perlin(53.5),
+                perlin(43.5),
+                perlin(23.5),
+            ]),
+            WorldPreset::Continent => NoiseNode::Sum(vec![
+                (
+                    0.5,
+                    NoiseNode::Warp {
+                        source: Box::new(NoiseNode::Fbm {
+                            scale: 250.0,
+                            octaves: 5,
+                            seed: 0,
+                        }),
+                        scale: 120.0,
+                        strength: 40.0,
+                        seed: 1,
+                    },
+                ),
+                (
+                    0.2,
+                    NoiseNode::Ridged {
+                        scale: 180.0,
+                        octaves: 4,
+                        seed: 2,
+                    },
+                ),
+                (0.5, NoiseNode::Falloff { radius: 1200.0 }),
+            ]),
+            WorldPreset::Archipelago => NoiseNode::Sum(vec![
+                (
+                    0.6,
+                    NoiseNode::Warp {
+                        source: Box::new(NoiseNode::Fbm {
+                            scale: 70.0,
+                            octaves: 4,
+                            seed: 0,
+                        }),
+                        scale: 40.0,
+                        strength: 12.0,
+                        seed: 1,
+                    },
+                ),
+                (0.25, NoiseNode::Falloff { radius: 2000.0 }),
+                (1.0, NoiseNode::Constant(-0.25)),
+            ]),
+            WorldPreset::InlandLakes => NoiseNode::Sum(vec![
+                (
+                    0.6,
+                    NoiseNode::Fbm {
+                        scale: 110.0,
+                        octaves: 4,
+                        seed: 0,
+                    },
+                ),
+                (1.0, NoiseNode::Constant(0.3)),
+            ]),
+        };
+        WorldGenSettings {
+            elevation,
+            rivers: RiverSettings::default(),
+            settlements: SettlementSettings::default(),
+            bounds: WorldBounds::default(),
+        }
+    }
+}
+
+impl FromStr for WorldPreset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        WorldPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = WorldPreset::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown world preset `{name}`, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl WorldGenSettings {
+    pub fn build_elevation(&self, gen_seed: u32) -> Elevation {
+        let elevation = self.elevation.build(gen_seed);
+        match self.bounds {
+            WorldBounds::Infinite => elevation,
+            WorldBounds::Grid => Box::new(EdgeFalloff {
+                source: elevation,
+                wobble: Perlin::new(gen_seed.wrapping_add(5)),
+            }),
+        }
+    }
+}
+
+impl WorldBounds {
+    pub fn contains(self, pos: GridPos) -> bool {
+        match self {
+            WorldBounds::Infinite => true,
+            WorldBounds::Grid => {
+                (0..GRID_COLS as i32).contains(&pos.x) && (0..GRID_ROWS as i32).contains(&pos.y)
+            }
+        }
+    }
+
+    /// Whether any tile of the chunk is in the world
+    pub fn contains_chunk(self, chunk: ChunkPos) -> bool {
+        let (first, last) = (
+            chunk.origin(),
+            chunk
+                .origin()
+                .offset(CHUNK_W as i32 - 1, CHUNK_H as i32 - 1),
+        );
+        match self {
+            WorldBounds::Infinite => true,
+            WorldBounds::Grid => {
+                last.x >= 0
+                    && last.y >= 0
+                    && first.x < GRID_COLS as i32
+                    && first.y < GRID_ROWS as i32
+            }
+        }
+    }
+
+    /// World space the player and camera are kept in, `None` when there's no edge
+    pub fn world_rect(self) -> Option<Rect> {
+        match self {
+            WorldBounds::Infinite => None,
+            WorldBounds::Grid => {
+                let half = Vec2::new(
+                    (TILE_W * SPRITE_SCALE_FACTOR) as f32,
+                    (TILE_H * SPRITE_SCALE_FACTOR) as f32,
+                ) / 2.0;
+                let first = Vec2::from(GridPos::new(0, 0).to_world());
+                let last =
+                    Vec2::from(GridPos::new(GRID_COLS as i32 - 1, GRID_ROWS as i32 - 1).to_world());
+                Some(Rect::from_corners(
+                    first + half * Vec2::new(-1.0, 1.0),
+                    last + half * Vec2::new(1.0, -1.0),
+                ))
+            }
+        }
+    }
+}
+
+impl NoiseNode {
+    pub fn build(&self, gen_seed: u32) -> Elevation {
+        match self {
+            NoiseNode::Perlin { scale, seed } => Box::new(
+                ScalePoint::new(Perlin::new(gen_seed.wrapping_add(*seed))).set_scale(1.0 / scale),
+            ),
+            NoiseNode::Fbm {
+                scale,
+                octaves,
+                seed,
+            } => Box::new(
+                Fbm::<Perlin>::new(gen_seed.wrapping_add(*seed))
+                    .set_octaves(*octaves)
+                    .set_frequency(1.0 / scale),
+            ),
+            NoiseNode::Ridged {
+                scale,
+                octaves,
+                seed,
+            } => Box::new(
+                RidgedMulti::<Perlin>::new(gen_seed.wrapping_add(*seed))
+                    .set_octaves(*octaves)
+                    .set_frequency(1.0 / scale),
+            ),
+            NoiseNode::Warp {
+                source,
+                scale,
+                strength,
+                seed,
+            } => Box::new(
+                Turbulence::<_, Perlin>::new(source.build(gen_seed))
+                    .set_seed(gen_seed.wrapping_add(*seed))
+                    .set_frequency(1.0 / scale)
+                    .set_power(*strength),
+            ),
+            NoiseNode::Falloff { radius } => Box::new(Falloff {
+                center: WorldPos::new(0.0, 0.0).to_grid(),
+                radius: *radius,
+            }),
+            NoiseNode::Constant(value) => Box::new(Constant::new(*value)),
+            NoiseNode::Sum(inputs) => Box::new(WeightedSum(
+                inputs
+                    .iter()
+                    .map(|(weight, node)| (*weight, node.build(gen_seed)))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl NoiseFn<f64, 2> for Falloff {
+    fn get(&self, [x, y]: [f64; 2]) -> f64 {
+        let (dx, dy) = (x - self.center.x as f64, y - self.center.y as f64);
+        let t = ((dx * dx + dy * dy).sqrt() / self.radius).min(1.0);
+        1.0 - 2.0 * t * t
+    }
+}
+
+impl NoiseFn<f64, 2> for EdgeFalloff {
+    fn get(&self, [x, y]: [f64; 2]) -> f64 {
+        let edge = x
+            .min(GRID_COLS as f64 - 1.0 - x)
+            .min(y)
+            .min(GRID_ROWS as f64 - 1.0 - y);
+        if edge < 0.0 {
+            return -1.0;
+        }
+        let wobble = self.wobble.get([x / 90.5, y / 90.5]) * WORLD_EDGE_WOBBLE;
+        let t = ((edge + wobble) / WORLD_EDGE_FALLOFF).clamp(0.0, 1.0);
+        -1.0 + (self.source.get([x, y]) + 1.0) * t
+    }
+}
+
+impl NoiseFn<f64, 2> for WeightedSum {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        self.0
+            .iter()
+            .map(|(weight, noise)| weight * noise.get(point))
+            .sum()
+    }
+}
@@ -0,0 +1,336 @@
+//! The player and terrain plugins running in an `App` without a window or renderer
+use bevy::{
+    image::TextureAtlasPlugin, prelude::*, render::mesh::Mesh2d, state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use game::{
+    biome::BiomePlugin,
+    player::*,
+    sprite_atlas::SpriteAtlasPlugin,
+    terrain::{ChunkRect, CurrentChunks, TerrainPlugin},
+    world::{TerrainKind, WorldTiles},
+    worldgen::{WorldBounds, WorldGenSettings},
+    ChunkPos, GridPos, WorldPos,
+};
+use std::{collections::HashSet, thread, time::Duration};
+
+const SEED: u32 = 42;
+/// Chunks are generated on other threads, give up on them after this many frames
+const MAX_FRAMES: usize = 3000;
+/// Game time each update advances by
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        ImagePlugin::default(),
+        TextureAtlasPlugin,
+        TransformPlugin,
+        StatesPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_asset::<ColorMaterial>()
+    .init_resource::<ButtonInput<KeyCode>>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME_TIME))
+    .add_plugins((
+        SpriteAtlasPlugin,
+        BiomePlugin,
+        TerrainPlugin { seed: Some(SEED) },
+        PlayerPlugin,
+    ));
+    app.world_mut().spawn((
+        Camera2d,
+        Projection::from(OrthographicProjection::default_2d()),
+    ));
+    app.finish();
+    app.cleanup();
+    app
+}
+
+fn run_until(app: &mut App, mut done: impl FnMut(&mut App) -> bool) {
+    for _ in 0..MAX_FRAMES {
+        app.update();
+        if done(app) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    panic!("gave up after {MAX_FRAMES} frames");
+}
+
+/// Runs until the chunks around the player are generated and spawned
+fn loaded_app() -> App {
+    let mut app = headless_app();
+    run_until(&mut app, |app| {
+        let player = app.world().resource::<CurrentPlayerChunkPosition>().0;
+        let tiles = app.world().resource::<WorldTiles>();
+        (-1..=1).all(|i| (-1..=1).all(|j| tiles.0.contains_key(&player.offset(i, j))))
+    });
+    app
+}
+
+fn player_state(app: &App) -> PlayerState {
+    app.world().resource::<State<PlayerState>>().get().clone()
+}
+
+fn player_translation(app: &mut App) -> Vec2 {
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&PhysicalTranslation, With<Player>>();
+    query.single(app.world()).unwrap().0
+}
+
+fn move_player(app: &mut App, pos: GridPos) {
+    let mut query = app.world_mut().query_filtered::<(
+        &mut PhysicalTranslation,
+        &mut PreviousPhysicalTranslation,
+    ), With<Player>>();
+    let (mut current, mut previous) = query.single_mut(app.world_mut()).unwrap();
+    current.0 = pos.to_world().into();
+    previous.0 = current.0;
+}
+
+/// A tile of the spawn chunk with `run` tiles to its right all of the terrain `kind`
+fn find_tile(app: &App, kind: TerrainKind, run: i32) -> GridPos {
+    let spawn = WorldPos::new(0.0, 0.0).to_chunk();
+    let data = &app.world().resource::<WorldTiles>().0[&spawn];
+    data.iter()
+        .map(|(pos, _)| pos)
+        .find(|pos| {
+            (0..=run).all(|i| {
+                let next = pos.offset(i, 0);
+                data.contains(next) && data.terrain_at(next) == kind
+            })
+        })
+        .unwrap_or_else(|| panic!("no {kind:?} in the spawn chunk"))
+}
+
+fn press(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+}
+
+/// Chunks with tiles and the ones with entities drawing them
+fn loaded_chunks(app: &App) -> (HashSet<ChunkPos>, HashSet<ChunkPos>) {
+    let tiles = app.world().resource::<WorldTiles>();
+    let current = app.world().resource::<CurrentChunks>();
+    (
+        tiles.0.keys().copied().collect(),
+        current.0.keys().copied().collect(),
+    )
+}
+
+fn chunk_meshes(app: &mut App) -> HashSet<Entity> {
+    let mut query = app.world_mut().query_filtered::<Entity, With<Mesh2d>>();
+    query.iter(app.world()).collect()
+}
+
+#[test]
+fn player_chunk_is_tracked() {
+    let mut app = headless_app();
+    app.update();
+
+    let spawn = WorldPos::new(0.0, 0.0).to_chunk();
+    assert_eq!(
+        app.world().resource::<CurrentPlayerChunkPosition>().0,
+        spawn
+    );
+
+    let next = spawn.offset(1, 0);
+    move_player(&mut app, next.origin().offset(5, 5));
+    app.update();
+    assert_eq!(app.world().resource::<CurrentPlayerChunkPosition>().0, next);
+}
+
+#[test]
+fn chunks_spawn_around_the_player_and_despawn_behind() {
+    let mut app = loaded_app();
+    let spawn = WorldPos::new(0.0, 0.0).to_chunk();
+    // The view is smaller than a chunk, only the chunks around the player load
+    let around_spawn: HashSet<_> = ChunkRect::around(spawn, 1).iter().collect();
+    run_until(&mut app, |app| {
+        loaded_chunks(app).1.is_superset(&around_spawn)
+    });
+    let (tiles, current) = loaded_chunks(&app);
+    assert_eq!(tiles.len(), 9);
+    assert_eq!(tiles, around_spawn);
+    assert_eq!(current, around_spawn);
+    let meshes = chunk_meshes(&mut app);
+    assert!(!meshes.is_empty());
+
+    // Far enough that nothing around the spawn is kept
+    let far = spawn.offset(10, 0);
+    move_player(&mut app, far.origin().offset(5, 5));
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&mut Transform, With<Camera2d>>();
+    query.single_mut(app.world_mut()).unwrap().translation =
+        Vec2::from(far.origin().offset(5, 5).to_world()).extend(0.0);
+    let around_far: HashSet<_> = ChunkRect::around(far, 1).iter().collect();
+    run_until(&mut app, |app| {
+        loaded_chunks(app).1.is_superset(&around_far)
+    });
+
+    let (tiles, current) = loaded_chunks(&app);
+    assert_eq!(tiles.len(), 9);
+    assert_eq!(tiles, around_far);
+    assert_eq!(current, around_far);
+    let remaining = chunk_meshes(&mut app);
+    assert!(meshes.is_disjoint(&remaining));
+    assert!(!remaining.is_empty());
+}
+
+#[test]
+fn walking_on_land() {
+    let mut app = loaded_app();
+    let land = find_tile(&app, TerrainKind::Grass, 1);
+    move_player(&mut app, land);
+    run_until(&mut app, |app| player_state(app) == PlayerState::Idle);
+
+    let start = player_translation(&mut app);
+    press(&mut app, KeyCode::KeyD);
+    app.update();
+    app.update();
+
+    assert_eq!(player_state(&app), PlayerState::Walk);
+    let moved = player_translation(&mut app) - start;
+    assert_eq!(moved.y, 0.0);
+    assert!(moved.x > 0.0);
+
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::KeyD);
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), PlayerState::Idle);
+}
+
+#[test]
+fn walking_speed_does_not_depend_on_frame_rate() {
+    let mut app = loaded_app();
+    // A second of walking crosses this many tiles and a bit
+    let land = find_tile(&app, TerrainKind::Grass, 6);
+
+    let mut walked = Vec::new();
+    for fps in [32, 128] {
+        move_player(&mut app, land);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release_all();
+        run_until(&mut app, |app| {
+            matches!(player_state(app), PlayerState::Idle | PlayerState::Walk)
+        });
+
+        let frame = Duration::from_secs(1) / fps;
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+        // The frame just set only counts from the next update on
+        app.update();
+        let start = player_translation(&mut app);
+        press(&mut app, KeyCode::KeyD);
+        for _ in 0..fps {
+            app.update();
+        }
+        walked.push(player_translation(&mut app).x - start.x);
+    }
+
+    for distance in walked {
+        assert!((distance - PLAYER_SPEED).abs() < 0.01, "walked {distance}");
+    }
+}
+
+#[test]
+fn jumping_into_water_and_swimming() {
+    let mut app = loaded_app();
+    let land = find_tile(&app, TerrainKind::Grass, 1);
+    move_player(&mut app, land);
+    run_until(&mut app, |app| player_state(app) == PlayerState::Idle);
+
+    let water = find_tile(&app, TerrainKind::Ocean, 1);
+    move_player(&mut app, water);
+    app.update();
+    app.update();
+    assert_eq!(player_state(&app), PlayerState::Jump);
+
+    // No steering mid air
+    let start = player_translation(&mut app);
+    press(&mut app, KeyCode::KeyD);
+    app.update();
+    assert_eq!(player_translation(&mut app), start);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(KeyCode::KeyD);
+
+    // The jump waits out game time, so it holds while the game is paused
+    let jump_frames = (PLAYER_JUMP_TIME / FRAME_TIME.as_secs_f32()).ceil() as usize;
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    for _ in 0..jump_frames * 2 {
+        app.update();
+    }
+    assert_eq!(player_state(&app), PlayerState::Jump);
+
+    app.world_mut().resource_mut::<Time<Virtual>>().unpause();
+    for _ in 0..jump_frames {
+        app.update();
+    }
+    assert_eq!(player_state(&app), PlayerState::Swim);
+}
+
+#[test]
+fn camera_stops_at_the_edge_of_a_bounded_world() {
+    let mut app = headless_app();
+    app.update();
+    app.world_mut().resource_mut::<WorldGenSettings>().bounds = WorldBounds::Grid;
+    let world = WorldBounds::Grid.world_rect().unwrap();
+    // No window to size the view from
+    let view = Rect::new(-400.0, -300.0, 400.0, 300.0);
+    let mut query = app.world_mut().query::<&mut Projection>();
+    if let Projection::Orthographic(ortho) = query.single_mut(app.world_mut()).unwrap().as_mut() {
+        ortho.area = view;
+    }
+
+    move_player(&mut app, GridPos::new(0, 0));
+    let mut query = app
+        .world_mut()
+        .query_filtered::<&Transform, With<Camera2d>>();
+    for _ in 0..300 {
+        app.update();
+        let camera = query.single(app.world()).unwrap().translation.xy();
+        let shown = Rect::from_center_half_size(camera, view.half_size());
+        assert_eq!(shown.union(world), world, "showing {shown:?}");
+    }
+    let camera = query.single(app.world()).unwrap().translation.xy();
+    let corner = vec2(world.min.x, world.max.y) + view.half_size() * vec2(1.0, -1.0);
+    assert!(camera.distance(corner) < 1.0, "camera at {camera}");
+}
+
+#[test]
+fn camera_catches_up_the_same_whatever_the_frame_rate() {
+    let mut app = headless_app();
+    app.update();
+    let target = WorldPos::new(0.0, 0.0).to_grid().offset(20, 0);
+    let distance = Vec2::from(target.to_world()).x;
+
+    let mut caught_up = Vec::new();
+    for fps in [32, 128] {
+        let frame = Duration::from_secs(1) / fps;
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+        app.update();
+        move_player(&mut app, target);
+        let mut query = app
+            .world_mut()
+            .query_filtered::<&mut Transform, With<Camera2d>>();
+        query.single_mut(app.world_mut()).unwrap().translation = Vec3::ZERO;
+        for _ in 0..fps {
+            app.update();
+        }
+        caught_up.push(query.single(app.world()).unwrap().translation.x / distance);
+    }
+
+    let expected = 1.0 - (-CAMERA_FOLLOW_RATE).exp();
+    for share in caught_up {
+        assert!((share - expected).abs() < 0.01, "caught up {share}");
+    }
+}
@@ -0,0 +1,81 @@
+use game::{
+    autotile::*,
+    biome::BiomeRegistry,
+    chunk_delta::{ChunkDeltas, TileEditEvent},
+    sprite_atlas::SpriteRegistry,
+    terrain::*,
+    world::*,
+    worldgen::WorldGenSettings,
+    ChunkPos, GridPos, CHUNK_W,
+};
+
+fn biomes() -> BiomeRegistry {
+    BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap()
+}
+
+fn sprites() -> SpriteRegistry {
+    SpriteRegistry::from_ron(include_str!("../assets/sprite-sheet.atlas.ron")).unwrap()
+}
+
+#[test]
+fn neighbours_follow_the_grid_axes() {
+    let center = GridPos::new(0, 0);
+    let below = Neighbours::of(center, |p| p == GridPos::new(0, 1));
+    assert_eq!(below, Neighbours(S));
+    let top_left = Neighbours::of(center, |p| p == GridPos::new(-1, -1));
+    assert_eq!(top_left, Neighbours(NW));
+}
+
+#[test]
+fn inner_corners_need_both_sides() {
+    assert_eq!(Neighbours(u8::MAX).inner_corners(), 0);
+    assert_eq!(Neighbours(!NW).inner_corners(), 1);
+    assert_eq!(Neighbours(!SE & !SW).inner_corners(), 4 | 8);
+    // Without the side tiles it is an outer corner instead
+    assert_eq!(Neighbours(!NW & !N).inner_corners(), 0);
+}
+
+#[test]
+fn shore_sprites_cover_every_neighbourhood() {
+    let sprites = sprites();
+    let ground = sprites.get("ground").cell;
+    let inner = sprites.get("shore.inner");
+
+    assert_eq!(shore_sprite(Neighbours(u8::MAX), &sprites), ground);
+    assert_eq!(shore_sprite(Neighbours(!SE), &sprites), inner.frame(3));
+    assert_eq!(
+        shore_sprite(Neighbours(S | SE | E), &sprites),
+        sprites.get("shore.top_left").cell
+    );
+    assert_eq!(
+        shore_sprite(Neighbours(0), &sprites),
+        sprites.get("shore.island").cell
+    );
+    for bits in 0..=u8::MAX {
+        shore_sprite(Neighbours(bits), &sprites);
+    }
+}
+
+#[test]
+fn edits_on_a_chunk_edge_reach_the_neighbour_apron() {
+    let edge = GridPos::new(CHUNK_W as i32 - 1, 10);
+    let mut deltas = ChunkDeltas::default();
+    for tile in [TerrainKind::Ocean, TerrainKind::Grass] {
+        deltas.record(&TileEditEvent {
+            pos: edge,
+            tile: TileData::new(tile),
+        });
+
+        let mut right = gen_chunk(
+            42,
+            ChunkPos::new(1, 0),
+            &WorldGenSettings::default(),
+            &biomes(),
+        );
+        for delta in deltas.around(right.pos) {
+            delta.apply(&mut right);
+        }
+        assert_eq!(right.terrain_at(edge), tile);
+        assert_eq!(right.get(edge), None);
+    }
+}
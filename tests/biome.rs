@@ -0,0 +1,78 @@
+use game::{biome::*, terrain::gen_chunk, world::Feature, worldgen::WorldGenSettings, ChunkPos};
+
+fn biomes() -> BiomeRegistry {
+    BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap()
+}
+
+#[test]
+fn config_defines_every_biome() {
+    let names: Vec<_> = biomes().biomes.into_iter().map(|b| b.name).collect();
+    for name in [
+        "desert",
+        "tundra",
+        "swamp",
+        "grassland",
+        "forest",
+        "mountains",
+    ] {
+        assert!(names.iter().any(|n| n == name), "missing biome {name}");
+    }
+}
+
+#[test]
+fn unmatched_climate_falls_back_to_the_last_biome() {
+    let biomes = biomes();
+    let climate = Climate {
+        elevation: 0.1,
+        moisture: -0.5,
+        ..Default::default()
+    };
+    assert_eq!(biomes.biome_at(&climate), biomes.biomes.len() - 1);
+}
+
+#[test]
+fn spawn_table_rows_add_up() {
+    let biome: Biome = ron::from_str(
+        "(name: \"test\", features: [
+            (feature: Rocks, chance: 0.25),
+            (feature: Cactus, chance: 0.5, patches: (0.0, 1.0)),
+        ])",
+    )
+    .unwrap();
+    let climate = Climate {
+        patches: 0.5,
+        ..Default::default()
+    };
+
+    assert_eq!(biome.pick_feature(&climate, 0.1), Some(Feature::Rocks));
+    assert_eq!(biome.pick_feature(&climate, 0.6), Some(Feature::Cactus));
+    assert_eq!(biome.pick_feature(&climate, 0.8), None);
+    let outside = Climate {
+        patches: -0.5,
+        ..climate
+    };
+    assert_eq!(biome.pick_feature(&outside, 0.6), None);
+}
+
+#[test]
+fn generated_world_has_several_biomes() {
+    let biomes = biomes();
+    let mut seen = vec![0; biomes.biomes.len()];
+    for x in -3..3 {
+        for y in -3..3 {
+            for (_, tile) in gen_chunk(
+                7,
+                ChunkPos::new(x, y),
+                &WorldGenSettings::default(),
+                &biomes,
+            )
+            .iter()
+            {
+                if tile.terrain.is_land() {
+                    seen[tile.biome as usize] += 1;
+                }
+            }
+        }
+    }
+    assert!(seen.iter().filter(|n| **n > 0).count() >= 4);
+}
@@ -0,0 +1,76 @@
+use game::*;
+use proptest::prelude::*;
+
+const TILE: (f32, f32) = (
+    (TILE_W * SPRITE_SCALE_FACTOR) as f32,
+    (TILE_H * SPRITE_SCALE_FACTOR) as f32,
+);
+
+#[test]
+fn world_origin_is_the_centre_of_the_initial_grid() {
+    let centre = GridPos::new(GRID_COLS as i32 / 2, GRID_ROWS as i32 / 2);
+    assert_eq!(WorldPos::new(0.0, 0.0).to_grid(), centre);
+    assert_eq!(centre.to_world(), WorldPos::new(0.0, 0.0));
+}
+
+#[test]
+fn grid_y_grows_downwards() {
+    let pos = GridPos::new(3, 3);
+    assert!(pos.offset(0, 1).to_world().y < pos.to_world().y);
+    assert!(pos.offset(1, 0).to_world().x > pos.to_world().x);
+}
+
+#[test]
+fn positions_near_a_tile_centre_stay_on_that_tile() {
+    let pos = GridPos::new(-7, -3);
+    let centre = pos.to_world();
+    for (dx, dy) in [(-1.0, 0.0), (0.0, -1.0), (-1.0, 1.0), (1.0, -1.0)] {
+        let nudged = WorldPos::new(centre.x + dx, centre.y + dy);
+        assert_eq!(nudged.to_grid(), pos);
+    }
+}
+
+#[test]
+fn chunks_split_negative_tiles_like_positive_ones() {
+    assert_eq!(GridPos::new(-1, -1).to_chunk(), ChunkPos::new(-1, -1));
+    assert_eq!(
+        GridPos::new(-1, -1).to_local(),
+        LocalTilePos {
+            x: CHUNK_W as u32 - 1,
+            y: CHUNK_H as u32 - 1
+        }
+    );
+    assert_eq!(
+        GridPos::new(-(CHUNK_W as i32), 0).to_chunk(),
+        ChunkPos::new(-1, 0)
+    );
+}
+
+proptest! {
+    #[test]
+    fn grid_world_grid_round_trips(x in -1_000_000..1_000_000i32, y in -1_000_000..1_000_000i32) {
+        let pos = GridPos::new(x, y);
+        prop_assert_eq!(pos.to_world().to_grid(), pos);
+    }
+
+    #[test]
+    fn world_grid_world_stays_within_half_a_tile(x in -1.0e6..1.0e6f32, y in -1.0e6..1.0e6f32) {
+        let centre = WorldPos::new(x, y).to_grid().to_world();
+        prop_assert!((centre.x - x).abs() <= TILE.0 / 2.0 + 0.01);
+        prop_assert!((centre.y - y).abs() <= TILE.1 / 2.0 + 0.01);
+    }
+
+    #[test]
+    fn chunk_and_local_rebuild_the_tile(x in -1_000_000..1_000_000i32, y in -1_000_000..1_000_000i32) {
+        let pos = GridPos::new(x, y);
+        let local = pos.to_local();
+        prop_assert!(local.x < CHUNK_W as u32 && local.y < CHUNK_H as u32);
+        prop_assert_eq!(pos.to_chunk().tile(local), pos);
+    }
+
+    #[test]
+    fn world_to_chunk_matches_its_tile(x in -1.0e6..1.0e6f32, y in -1.0e6..1.0e6f32) {
+        let pos = WorldPos::new(x, y);
+        prop_assert_eq!(pos.to_chunk(), pos.to_grid().to_chunk());
+    }
+}
@@ -0,0 +1,53 @@
+use game::{
+    biome::BiomeRegistry,
+    sprite_atlas::SpriteRegistry,
+    terrain::gen_chunk,
+    world::{Feature, Mineral},
+    worldgen::WorldGenSettings,
+    ChunkPos,
+};
+use std::collections::HashSet;
+
+const MINERALS: [Mineral; 5] = [
+    Mineral::Stone,
+    Mineral::Iron,
+    Mineral::Coal,
+    Mineral::Gold,
+    Mineral::Clay,
+];
+
+#[test]
+fn every_mineral_has_its_own_sprite() {
+    let sprites =
+        SpriteRegistry::from_ron(include_str!("../assets/sprite-sheet.atlas.ron")).unwrap();
+    let cells: HashSet<_> = MINERALS
+        .iter()
+        .map(|m| sprites.get(Feature::Deposit(*m).sprite_name()).cell)
+        .collect();
+    assert_eq!(cells.len(), MINERALS.len());
+}
+
+#[test]
+fn generated_deposits_hold_a_finite_quantity() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let mut found = HashSet::new();
+    for x in -4..4 {
+        for y in -4..4 {
+            let chunk = gen_chunk(
+                3,
+                ChunkPos::new(x, y),
+                &WorldGenSettings::default(),
+                &biomes,
+            );
+            for (_, tile) in chunk.iter() {
+                if let Some(Feature::Deposit(mineral)) = tile.feature {
+                    assert!(tile.terrain.is_land());
+                    assert!(tile.quantity > 0);
+                    assert!(tile.quantity < 2 * tile.feature.unwrap().initial_quantity());
+                    found.insert(mineral);
+                }
+            }
+        }
+    }
+    assert!(found.len() >= 3, "only found {found:?}");
+}
@@ -0,0 +1,96 @@
+use game::{
+    biome::BiomeRegistry,
+    chunk_delta::{ChunkDeltas, TileEditEvent},
+    fertility::{fertility_layer, FERTILITY_WATER_REACH},
+    hydrology::RiverCache,
+    terrain::{generate_chunk, generate_edited_chunk},
+    world::{TerrainKind, TileData},
+    worldgen::WorldGenSettings,
+    ChunkPos, GridPos,
+};
+
+#[test]
+fn water_in_the_next_chunk_waters_the_border() {
+    // A river running down just left of chunk (0, 0)
+    let layer = fertility_layer(ChunkPos::new(0, 0), |p| p.x != -1, |_| 0.4);
+    let at = |x| layer.get(GridPos::new(x, 10)).unwrap();
+
+    for x in 0..FERTILITY_WATER_REACH {
+        assert!(at(x) > at(x + 1));
+    }
+    assert_eq!(at(FERTILITY_WATER_REACH), at(FERTILITY_WATER_REACH + 5));
+    assert_eq!(layer.get(GridPos::new(-1, 10)), None);
+}
+
+#[test]
+fn generated_fertility_is_zero_on_water() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let generated = generate_chunk(11, ChunkPos::new(0, 0), &settings, &biomes);
+
+    let mut fertile = 0;
+    for (pos, tile) in generated.data.iter() {
+        let value = generated.fertility.get(pos).unwrap();
+        assert!((0.0..=1.0).contains(&value));
+        if !tile.terrain.is_land() && !tile.terrain.is_fresh_water() {
+            assert_eq!(value, 0.0);
+        }
+        if value > 0.5 {
+            fertile += 1;
+        }
+    }
+    assert!(fertile > 0);
+}
+
+#[test]
+fn edited_tiles_change_fertility() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let chunk = ChunkPos::new(0, 0);
+    let generated = generate_chunk(11, chunk, &settings, &biomes);
+
+    // Grass with no water within reach
+    let reach = FERTILITY_WATER_REACH;
+    let dry = generated
+        .data
+        .iter()
+        .map(|(pos, _)| pos)
+        .find(|pos| {
+            (-reach..=reach).all(|i| {
+                (-reach..=reach).all(|j| {
+                    let p = pos.offset(i, j);
+                    generated.data.contains(p) && generated.data.terrain_at(p).is_land()
+                })
+            })
+        })
+        .unwrap();
+    let sea = generated
+        .data
+        .iter()
+        .find(|(pos, tile)| tile.terrain == TerrainKind::Ocean && pos.to_chunk() == chunk)
+        .map(|(pos, _)| pos)
+        .unwrap();
+
+    let mut deltas = ChunkDeltas::default();
+    for (pos, tile) in [
+        (dry.offset(1, 0), TileData::new(TerrainKind::Lake)),
+        (sea, TileData::new(TerrainKind::Grass)),
+    ] {
+        deltas.record(&TileEditEvent { pos, tile });
+    }
+    let edited = generate_edited_chunk(
+        11,
+        chunk,
+        &settings,
+        &biomes,
+        &deltas.around(chunk),
+        &RiverCache::default(),
+    );
+
+    let before = |p| generated.fertility.get(p).unwrap();
+    let after = |p| edited.fertility.get(p).unwrap();
+    assert_eq!(after(dry.offset(1, 0)), 0.0);
+    assert!(after(dry) > before(dry));
+    assert_eq!(before(sea), 0.0);
+    assert!(after(sea) > 0.0);
+}
@@ -0,0 +1,52 @@
+use game::{hydrology::*, world::TerrainKind, worldgen::WorldPreset, GridPos};
+use noise::NoiseFn;
+
+fn water(min: GridPos, max: GridPos) -> std::collections::HashMap<GridPos, TerrainKind> {
+    let settings = WorldPreset::Classic.settings();
+    let elevation = settings.build_elevation(7);
+    trace_fresh_water(7, &elevation, &settings.rivers, min, max)
+}
+
+#[test]
+fn rivers_and_lakes_are_generated() {
+    let water = water(GridPos::new(-400, -400), GridPos::new(400, 400));
+    assert!(water.values().any(|k| *k == TerrainKind::River));
+    assert!(water.values().any(|k| *k == TerrainKind::Lake));
+}
+
+#[test]
+fn overlapping_areas_agree() {
+    let left = water(GridPos::new(-300, 0), GridPos::new(10, 100));
+    let right = water(GridPos::new(-10, 0), GridPos::new(300, 100));
+    for x in -10..10 {
+        for y in 0..100 {
+            let pos = GridPos::new(x, y);
+            assert_eq!(left.get(&pos), right.get(&pos), "{pos:?}");
+        }
+    }
+}
+
+#[test]
+fn fresh_water_only_covers_land() {
+    let settings = WorldPreset::Classic.settings();
+    let elevation = settings.build_elevation(7);
+    let water = water(GridPos::new(-400, -400), GridPos::new(400, 400));
+    for (pos, kind) in water.iter() {
+        assert!(kind.is_fresh_water());
+        assert!(elevation.get([pos.x as f64, pos.y as f64]) > 0.0);
+    }
+}
+
+#[test]
+fn cached_rivers_match_tracing_afresh() {
+    let settings = WorldPreset::Classic.settings();
+    let elevation = settings.build_elevation(7);
+    let rivers = RiverCache::default();
+    let area = |min, max| rivers.fresh_water(7, &elevation, &settings.rivers, min, max);
+
+    let (min, max) = (GridPos::new(-200, -100), GridPos::new(0, 0));
+    assert_eq!(area(min, max), water(min, max));
+    // Mostly rivers traced for the first area
+    let (min, max) = (GridPos::new(-100, -50), GridPos::new(100, 50));
+    assert_eq!(area(min, max), water(min, max));
+}
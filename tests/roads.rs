@@ -0,0 +1,63 @@
+use game::{
+    biome::BiomeRegistry,
+    hydrology::RiverCache,
+    roads::{find_road, roads_near, RoadPlan},
+    terrain::generate_chunk,
+    worldgen::WorldGenSettings,
+    GridPos,
+};
+
+#[test]
+fn road_goes_around_what_it_cant_cross() {
+    let road = RoadPlan {
+        from: GridPos::new(0, 5),
+        to: GridPos::new(10, 5),
+        min: GridPos::new(0, 0),
+        max: GridPos::new(11, 11),
+    };
+    // A wall down x = 5 with a gap at the bottom
+    let path = find_road(&road, |p| (p.x != 5 || p.y == 9).then_some(1)).unwrap();
+
+    assert_eq!(path.first(), Some(&road.from));
+    assert_eq!(path.last(), Some(&road.to));
+    assert_eq!(path.len(), 19);
+    for pair in path.windows(2) {
+        assert_eq!(
+            pair[0].x.abs_diff(pair[1].x) + pair[0].y.abs_diff(pair[1].y),
+            1
+        );
+    }
+    assert!(path.contains(&GridPos::new(5, 9)));
+
+    let walled = find_road(&road, |p| (p.x != 5).then_some(1));
+    assert_eq!(walled, None);
+}
+
+#[test]
+fn villages_are_joined_by_roads() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let elevation = settings.build_elevation(5);
+    let roads = roads_near(
+        5,
+        &elevation,
+        &RiverCache::default(),
+        &settings,
+        GridPos::new(-500, -500),
+        GridPos::new(500, 500),
+    );
+    let road = roads
+        .iter()
+        .find(|road| find_road(road, |_| Some(1)).is_some())
+        .unwrap();
+
+    // Both ends of the road have a road tile leading out of the market
+    for end in [road.from, road.to] {
+        let generated = generate_chunk(5, end.to_chunk(), &settings, &biomes);
+        let leading_out = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .chain(&[(0, -2), (2, 0), (0, 2), (-2, 0)])
+            .any(|(i, j)| generated.data.road_at(end.offset(*i, *j)));
+        assert!(leading_out);
+    }
+}
@@ -0,0 +1,36 @@
+use game::{
+    chunk_delta::{ChunkDeltas, TileEditEvent},
+    save::*,
+    world::*,
+    worldgen::WorldPreset,
+    ChunkPos, GridPos,
+};
+
+#[test]
+fn save_game_round_trips_through_disk() {
+    let mut chunk_deltas = ChunkDeltas::default();
+    chunk_deltas.record(&TileEditEvent {
+        pos: GridPos::new(-410, 725),
+        tile: TileData::new(TerrainKind::Grass),
+    });
+    chunk_deltas.record(&TileEditEvent {
+        pos: GridPos::new(12, 3),
+        tile: TileData::new(TerrainKind::Shore).with_feature(Feature::House(HouseSize::Large), 1),
+    });
+
+    let save = SaveGame {
+        seed: 1234,
+        player: SavedPlayer {
+            translation: [-120.5, 3000.0, 2.0],
+            rotation: [0.0, 0.0, 0.38268343, 0.9238795],
+            state: SavedPlayerState::Swim,
+        },
+        chunk: ChunkPos::new(-3, 7),
+        chunk_deltas,
+        world_gen: WorldPreset::Archipelago.settings(),
+    };
+
+    let path = std::env::temp_dir().join("game-save-round-trip/world.ron");
+    save.write(&path).unwrap();
+    assert_eq!(SaveGame::read(&path).unwrap(), save);
+}
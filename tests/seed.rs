@@ -0,0 +1,16 @@
+use game::seed::seed_from_text;
+
+#[test]
+fn numbers_are_their_own_seed() {
+    assert_eq!(seed_from_text("1234"), 1234);
+    assert_eq!(seed_from_text(" 42\n"), 42);
+    assert_eq!(seed_from_text(&u32::MAX.to_string()), u32::MAX);
+}
+
+#[test]
+fn text_seeds_hash_the_same_every_time() {
+    // FNV-1a of "hello"
+    assert_eq!(seed_from_text("hello"), 0x4f9f2cab);
+    assert_ne!(seed_from_text("my world"), seed_from_text("my world 2"));
+    assert_eq!(seed_from_text("my world"), seed_from_text("my world"));
+}
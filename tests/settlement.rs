@@ -0,0 +1,122 @@
+use game::{
+    biome::BiomeRegistry,
+    hydrology::{trace_fresh_water, RiverCache, RiverSettings},
+    settlement::{villages_near, Village},
+    terrain::generate_chunk,
+    world::Feature,
+    worldgen::WorldGenSettings,
+    GridPos,
+};
+
+#[test]
+fn villages_are_houses_along_streets_around_a_market() {
+    let settings = WorldGenSettings::default();
+    let elevation = settings.build_elevation(5);
+    let villages = villages_near(
+        5,
+        &elevation,
+        &RiverCache::default(),
+        &settings,
+        GridPos::new(-1000, -1000),
+        GridPos::new(1000, 1000),
+    );
+    assert!(!villages.is_empty());
+
+    for village in villages.iter() {
+        let center = village.settlement.center;
+        assert!(!village.settlement.name.is_empty());
+        assert!(village.settlement.population > 0);
+        assert_eq!(village.tiles[&center].0, Feature::Market);
+
+        for (pos, (feature, _)) in village.tiles.iter() {
+            if let Feature::House(_) = feature {
+                let on_street = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                    .iter()
+                    .any(|(i, j)| village.streets.contains(&pos.offset(*i, *j)));
+                assert!(on_street, "house at {pos:?} away from the streets");
+            }
+        }
+
+        // Planned the same whichever area asked for it
+        let again = villages_near(
+            5,
+            &elevation,
+            &RiverCache::default(),
+            &settings,
+            center,
+            center.offset(1, 1),
+        );
+        assert_eq!(again, vec![village.clone()]);
+    }
+}
+
+#[test]
+fn settlement_comes_with_the_chunk_of_its_market() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let elevation = settings.build_elevation(5);
+    let villages = villages_near(
+        5,
+        &elevation,
+        &RiverCache::default(),
+        &settings,
+        GridPos::new(-1000, -1000),
+        GridPos::new(1000, 1000),
+    );
+    let village = &villages[0];
+    let center = village.settlement.center;
+
+    let generated = generate_chunk(5, center.to_chunk(), &settings, &biomes);
+    assert_eq!(generated.settlements, vec![village.settlement.clone()]);
+    let tile = generated.data.get(center).unwrap();
+    if tile.terrain.is_land() {
+        assert_eq!(tile.feature, Some(Feature::Market));
+    }
+}
+
+#[test]
+fn nothing_is_built_on_rivers_or_lakes() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let elevation = settings.build_elevation(6);
+    let (min, max) = (GridPos::new(-600, -600), GridPos::new(600, 600));
+    let reach = settings.settlements.reach();
+    let water = trace_fresh_water(
+        6,
+        &elevation,
+        &settings.rivers,
+        min.offset(-reach, -reach),
+        max.offset(reach, reach),
+    );
+    let on_water = |village: &Village| village.tiles.keys().any(|pos| water.contains_key(pos));
+
+    // The villages there would be if the world had no rivers
+    let dry = WorldGenSettings {
+        rivers: RiverSettings {
+            source_chance: 0.0,
+            ..RiverSettings::default()
+        },
+        ..settings.clone()
+    };
+    let dry_villages = villages_near(6, &elevation, &RiverCache::default(), &dry, min, max);
+    let village = dry_villages
+        .iter()
+        .find(|v| {
+            v.tiles.iter().any(|(pos, (feature, _))| {
+                matches!(feature, Feature::House(_)) && water.contains_key(pos)
+            })
+        })
+        .expect("no village planned with houses over fresh water");
+
+    let villages = villages_near(6, &elevation, &RiverCache::default(), &settings, min, max);
+    assert!(!villages.iter().any(on_water));
+
+    let center = village.settlement.center;
+    let generated = generate_chunk(6, center.to_chunk(), &settings, &biomes);
+    let population = generated
+        .settlements
+        .iter()
+        .find(|s| s.center == center)
+        .map_or(0, |s| s.population);
+    assert!(population < village.settlement.population);
+}
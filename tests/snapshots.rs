@@ -0,0 +1,151 @@
+//! Generated chunks compared against fingerprints in `tests/fixtures/chunks`.
+//! After a change that is meant to alter worlds, rewrite the fixtures with
+//! `UPDATE_SNAPSHOTS=1 cargo test --test snapshots` and review their diff.
+use game::{
+    biome::BiomeRegistry,
+    terrain::{generate_chunk, GeneratedChunk},
+    world::*,
+    worldgen::WorldPreset,
+    ChunkPos, CHUNK_H, CHUNK_W,
+};
+use std::{collections::BTreeMap, fmt::Write, fs, path::PathBuf};
+
+/// Every this many tiles a character is drawn on the fixture's map
+const MAP_STEP: usize = 4;
+
+const SNAPSHOTS: [(WorldPreset, u32, ChunkPos); 6] = [
+    (WorldPreset::Classic, 42, ChunkPos::new(0, 0)),
+    (WorldPreset::Classic, 42, ChunkPos::new(2, 4)),
+    (WorldPreset::Classic, 7, ChunkPos::new(-3, -2)),
+    (WorldPreset::Continent, 1234, ChunkPos::new(2, 4)),
+    (WorldPreset::Archipelago, 3, ChunkPos::new(1, 1)),
+    (WorldPreset::InlandLakes, 9, ChunkPos::new(4, 3)),
+];
+
+fn tile_char(tile: &TileData) -> char {
+    match tile.feature {
+        Some(Feature::Tree(_)) => 't',
+        Some(Feature::DenseForest) => 'T',
+        Some(Feature::House(_)) => 'h',
+        Some(Feature::SettlementGround) => '#',
+        Some(Feature::Market) => 'M',
+        Some(Feature::Field) => '"',
+        Some(Feature::Mountain(_)) => '^',
+        Some(Feature::Rocks) => 'o',
+        Some(Feature::Cactus) => 'y',
+        Some(Feature::Reeds) => 'r',
+        Some(Feature::Deposit(_)) => '*',
+        None if tile.road => '+',
+        None => match tile.terrain {
+            TerrainKind::Ocean => '~',
+            TerrainKind::River | TerrainKind::Lake => '=',
+            TerrainKind::Shore => '.',
+            TerrainKind::Grass => ',',
+        },
+    }
+}
+
+/// Hash of every field of every tile, a coarse map to see what moved, and totals
+/// to see by how much
+fn fingerprint(chunk: &GeneratedChunk) -> String {
+    // FNV-1a, stays the same across builds and platforms
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut terrain = BTreeMap::new();
+    let mut features = BTreeMap::new();
+    let mut roads = 0;
+    for (_, tile) in chunk.data.iter() {
+        for byte in ron::to_string(tile).unwrap().bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        *terrain.entry(format!("{:?}", tile.terrain)).or_insert(0) += 1;
+        if let Some(feature) = tile.feature {
+            *features.entry(feature.sprite_name()).or_insert(0) += 1;
+        }
+        roads += tile.road as u32;
+    }
+
+    let mut out = String::new();
+    writeln!(out, "hash {hash:016x}").unwrap();
+    for (kind, count) in terrain {
+        writeln!(out, "terrain {kind} {count}").unwrap();
+    }
+    for (name, count) in features {
+        writeln!(out, "feature {name} {count}").unwrap();
+    }
+    writeln!(out, "roads {roads}").unwrap();
+    for settlement in &chunk.settlements {
+        let center = settlement.center;
+        writeln!(
+            out,
+            "settlement {} at {},{} population {}",
+            settlement.name, center.x, center.y, settlement.population
+        )
+        .unwrap();
+    }
+
+    let origin = chunk.data.pos.origin();
+    for y in (0..CHUNK_H).step_by(MAP_STEP) {
+        let row: String = (0..CHUNK_W)
+            .step_by(MAP_STEP)
+            .map(|x| {
+                let pos = origin.offset(x as i32, y as i32);
+                tile_char(chunk.data.get(pos).unwrap())
+            })
+            .collect();
+        writeln!(out, "{row}").unwrap();
+    }
+    out
+}
+
+fn fixture_path(preset: WorldPreset, seed: u32, chunk: ChunkPos) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/chunks")
+        .join(format!(
+            "{}-{seed}-{}_{}.txt",
+            preset.name(),
+            chunk.x,
+            chunk.y
+        ))
+}
+
+#[test]
+fn generated_chunks_match_fixtures() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+
+    let mut changed = Vec::new();
+    for (preset, seed, chunk) in SNAPSHOTS {
+        let generated = generate_chunk(seed, chunk, &preset.settings(), &biomes);
+        let actual = fingerprint(&generated);
+        let path = fixture_path(preset, seed, chunk);
+        if update {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        if expected != actual {
+            eprintln!("{} differs, generated:\n{actual}", path.display());
+            changed.push(path);
+        }
+    }
+    assert!(
+        changed.is_empty(),
+        "generation changed for {changed:?}, run with UPDATE_SNAPSHOTS=1 if that's intended"
+    );
+}
+
+#[test]
+fn fingerprint_sees_a_single_tile() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let (preset, seed, chunk) = SNAPSHOTS[0];
+    let mut generated = generate_chunk(seed, chunk, &preset.settings(), &biomes);
+    let before = fingerprint(&generated);
+
+    let pos = chunk.origin().offset(1, 1);
+    let mut tile = *generated.data.get(pos).unwrap();
+    tile.variant = tile.variant.wrapping_add(1);
+    generated.data.set(pos, tile);
+    assert_ne!(fingerprint(&generated), before);
+}
@@ -0,0 +1,40 @@
+use game::{sprite_atlas::SpriteRegistry, SPRITE_SHEET_H, SPRITE_SHEET_W};
+
+#[test]
+fn manifest_cells_fit_in_the_sheet() {
+    let manifest = include_str!("../assets/sprite-sheet.atlas.ron");
+    let sprites = SpriteRegistry::from_ron(manifest).unwrap();
+
+    for name in [
+        "ground",
+        "shore.inner",
+        "shore.tip",
+        "tree.pine",
+        "house.small",
+        "player.walk",
+        "player.swim",
+    ] {
+        let entry = sprites.get(name);
+        assert!(entry.frames > 0);
+        assert!(entry.cell + entry.frames <= SPRITE_SHEET_W * SPRITE_SHEET_H);
+    }
+}
+
+#[test]
+fn manifest_has_every_required_sprite() {
+    let manifest = include_str!("../assets/sprite-sheet.atlas.ron");
+    assert_eq!(
+        SpriteRegistry::from_ron(manifest).unwrap().validate(),
+        Ok(())
+    );
+
+    let without_road = manifest.replace("\"road\"", "\"roads\"");
+    let sprites = SpriteRegistry::from_ron(&without_road).unwrap();
+    assert_eq!(sprites.validate(), Err("missing sprites road".to_string()));
+    // Still drawn, just with the wrong sprite
+    assert_eq!(sprites.get("road").frames, 1);
+
+    let without_reeds = manifest.replace("\"reeds\"", "\"reed\"");
+    let sprites = SpriteRegistry::from_ron(&without_reeds).unwrap();
+    assert_eq!(sprites.validate(), Err("missing sprites reeds".to_string()));
+}
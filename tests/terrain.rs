@@ -0,0 +1,143 @@
+use bevy::math::Rect;
+use game::{
+    biome::BiomeRegistry,
+    chunk_delta::{ChunkDeltas, TileEditEvent},
+    chunk_mesh::build_chunk_meshes,
+    sprite_atlas::SpriteRegistry,
+    terrain::*,
+    world::*,
+    worldgen::WorldGenSettings,
+    ChunkPos, GridPos, CHUNK_H, CHUNK_W, SPRITE_SHEET_H, SPRITE_SHEET_W,
+};
+
+fn biomes() -> BiomeRegistry {
+    BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap()
+}
+
+fn sprites() -> SpriteRegistry {
+    SpriteRegistry::from_ron(include_str!("../assets/sprite-sheet.atlas.ron")).unwrap()
+}
+
+#[test]
+fn same_seed_generates_same_chunk() {
+    for chunk in [
+        ChunkPos::new(0, 0),
+        ChunkPos::new(-1, 2),
+        ChunkPos::new(3, -4),
+    ] {
+        assert_eq!(
+            gen_chunk(42, chunk, &WorldGenSettings::default(), &biomes()),
+            gen_chunk(42, chunk, &WorldGenSettings::default(), &biomes())
+        );
+    }
+}
+
+#[test]
+fn different_seeds_generate_different_chunks() {
+    assert_ne!(
+        gen_chunk(
+            1,
+            ChunkPos::new(0, 0),
+            &WorldGenSettings::default(),
+            &biomes()
+        ),
+        gen_chunk(
+            2,
+            ChunkPos::new(0, 0),
+            &WorldGenSettings::default(),
+            &biomes()
+        )
+    );
+}
+
+#[test]
+fn apron_matches_neighbour_chunk() {
+    let (left, right) = (
+        gen_chunk(
+            42,
+            ChunkPos::new(0, 0),
+            &WorldGenSettings::default(),
+            &biomes(),
+        ),
+        gen_chunk(
+            42,
+            ChunkPos::new(1, 0),
+            &WorldGenSettings::default(),
+            &biomes(),
+        ),
+    );
+    for y in 0..CHUNK_H as i32 {
+        let (inside, outside) = (
+            GridPos::new(CHUNK_W as i32 - 1, y),
+            GridPos::new(CHUNK_W as i32, y),
+        );
+        assert_eq!(
+            left.terrain_at(outside),
+            right.get(outside).unwrap().terrain
+        );
+        assert_eq!(right.terrain_at(inside), left.get(inside).unwrap().terrain);
+    }
+}
+
+#[test]
+fn features_stay_on_land() {
+    let data = gen_chunk(
+        42,
+        ChunkPos::new(-1, 1),
+        &WorldGenSettings::default(),
+        &biomes(),
+    );
+    assert!(data.iter().any(|(_, t)| t.feature.is_some()));
+    for (_, tile) in data.iter() {
+        assert!(tile.feature.is_none() || tile.terrain.is_land());
+    }
+}
+
+#[test]
+fn chunk_meshes_have_a_quad_per_sprite() {
+    let tiles = chunk_sprites(
+        &gen_chunk(
+            42,
+            ChunkPos::new(0, 0),
+            &WorldGenSettings::default(),
+            &biomes(),
+        ),
+        &sprites(),
+    );
+    let uvs = vec![Rect::default(); SPRITE_SHEET_W * SPRITE_SHEET_H];
+    let layers = build_chunk_meshes(ChunkPos::new(0, 0), &tiles, &uvs);
+
+    let vertices: usize = layers.iter().map(|l| l.mesh.count_vertices()).sum();
+    assert_eq!(vertices, tiles.len() * 4);
+    assert!(layers.windows(2).all(|l| l[0].z_index < l[1].z_index));
+}
+
+#[test]
+fn chunk_deltas_apply_on_top_of_generation() {
+    let mut data = gen_chunk(
+        42,
+        ChunkPos::new(0, 0),
+        &WorldGenSettings::default(),
+        &biomes(),
+    );
+    let (tree_pos, _) = data
+        .iter()
+        .find(|(_, t)| matches!(t.feature, Some(Feature::Tree(_))))
+        .unwrap();
+    let house = TileData::new(TerrainKind::Grass).with_feature(Feature::House(HouseSize::Small), 1);
+
+    let mut deltas = ChunkDeltas::default();
+    for (pos, tile) in [
+        (tree_pos, TileData::new(TerrainKind::Grass)),
+        (GridPos::new(5, 5), house),
+    ] {
+        deltas.record(&TileEditEvent { pos, tile });
+    }
+    deltas.0[&ChunkPos::new(0, 0)].apply(&mut data);
+
+    assert_eq!(data.get(tree_pos).unwrap().feature, None);
+    assert_eq!(data.get(GridPos::new(5, 5)), Some(&house));
+    assert!(chunk_sprites(&data, &sprites())
+        .iter()
+        .any(|t| t.pos == GridPos::new(5, 5) && t.sprite == 17));
+}
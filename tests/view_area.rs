@@ -0,0 +1,52 @@
+use bevy::math::{Rect, Vec2};
+use game::{
+    terrain::{ChunkRect, ViewArea},
+    ChunkPos, GridPos,
+};
+
+#[test]
+fn zoomed_out_view_covers_more_chunks() {
+    let center = Vec2::from(GridPos::new(100, 50).to_world());
+    let view = |scale: f32| {
+        let half = Vec2::new(960.0, 540.0) * scale;
+        ChunkRect::covering(Rect::from_corners(center - half, center + half))
+    };
+
+    assert_eq!(view(1.0), ChunkRect::around(ChunkPos::new(0, 0), 0));
+    let far = view(7.5);
+    assert!(far.contains(ChunkPos::new(-1, -1)));
+    assert!(far.contains(ChunkPos::new(1, 1)));
+    assert!(far.iter().count() >= 9);
+}
+
+#[test]
+fn chunks_stay_loaded_a_while_after_leaving_view() {
+    let area = ViewArea {
+        player: ChunkPos::new(0, 0),
+        visible: Some(ChunkRect::around(ChunkPos::new(6, 0), 1)),
+        ..Default::default()
+    };
+
+    assert!(area.wants(ChunkPos::new(1, 1)));
+    assert!(area.wants(ChunkPos::new(7, 1)));
+    assert!(!area.wants(ChunkPos::new(2, 0)));
+    assert!(area.keeps(ChunkPos::new(2, 0)));
+    assert!(area.keeps(ChunkPos::new(8, 2)));
+    assert!(!area.keeps(ChunkPos::new(3, 0)));
+}
+
+#[test]
+fn ground_under_the_player_loads_first() {
+    let area = ViewArea {
+        player: ChunkPos::new(0, 0),
+        visible: Some(ChunkRect::around(ChunkPos::new(0, 0), 2)),
+        ..Default::default()
+    };
+    let chunks = area.chunks();
+
+    assert_eq!(chunks.len(), 25);
+    assert_eq!(chunks[0], ChunkPos::new(0, 0));
+    assert!(chunks[..9]
+        .iter()
+        .all(|c| ChunkRect::around(ChunkPos::new(0, 0), 1).contains(*c)));
+}
@@ -0,0 +1,37 @@
+use bevy::math::UVec2;
+use game::{
+    biome::BiomeRegistry,
+    terrain::gen_chunk,
+    world::{TerrainKind, TileData},
+    world_map::{render_world_map, tile_color},
+    worldgen::WorldGenSettings,
+    ChunkPos, CHUNK_H, CHUNK_W,
+};
+
+#[test]
+fn map_has_a_pixel_per_tile() {
+    let biomes = BiomeRegistry::from_ron(include_str!("../assets/default.biomes.ron")).unwrap();
+    let settings = WorldGenSettings::default();
+    let from = ChunkPos::new(-1, 0);
+    let image = render_world_map(9, from, UVec2::new(2, 1), &settings, &biomes);
+
+    assert_eq!(image.width() as usize, 2 * CHUNK_W);
+    assert_eq!(image.height() as usize, CHUNK_H);
+    let data = gen_chunk(9, ChunkPos::new(0, 0), &settings, &biomes);
+    let pixels = image.data.as_ref().unwrap();
+    for (pos, tile) in data.iter().step_by(97) {
+        let (x, y) = ((pos.x - from.origin().x) as usize, pos.y as usize);
+        let i = (y * 2 * CHUNK_W + x) * 4;
+        assert_eq!(pixels[i..i + 3], tile_color(tile));
+    }
+}
+
+#[test]
+fn water_and_land_look_different() {
+    let ocean = tile_color(&TileData::new(TerrainKind::Ocean));
+    let lake = tile_color(&TileData::new(TerrainKind::Lake));
+    let grass = tile_color(&TileData::new(TerrainKind::Grass));
+    assert_ne!(ocean, grass);
+    assert_ne!(lake, grass);
+    assert_ne!(ocean, lake);
+}
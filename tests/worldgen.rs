@@ -0,0 +1,112 @@
+use bevy::math::Vec2;
+use game::{worldgen::*, ChunkPos, GridPos, WorldPos, GRID_COLS, GRID_ROWS};
+use noise::{NoiseFn, Perlin};
+
+fn land_fraction(elevation: &Elevation, center: GridPos, radius: i32) -> f64 {
+    let mut land = 0;
+    let mut total = 0;
+    for x in (-radius..radius).step_by(7) {
+        for y in (-radius..radius).step_by(7) {
+            total += 1;
+            if elevation.get([(center.x + x) as f64, (center.y + y) as f64]) > 0.0 {
+                land += 1;
+            }
+        }
+    }
+    land as f64 / total as f64
+}
+
+#[test]
+fn classic_preset_matches_the_original_octaves() {
+    let perlin = Perlin::new(42);
+    let elevation = WorldPreset::Classic.settings().build_elevation(42);
+    for (x, y) in [(0.0, 0.0), (-317.0, 41.0), (1200.0, -999.0)] {
+        let original = (perlin.get([x / 100.5, y / 100.5])
+            + perlin.get([x / 53.5, y / 53.5])
+            + perlin.get([x / 43.5, y / 43.5])
+            + perlin.get([x / 23.5, y / 23.5]))
+            / 4.0;
+        assert!((elevation.get([x, y]) - original).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn presets_shape_the_world_differently() {
+    let center = WorldPos::new(0.0, 0.0).to_grid();
+    let far = GridPos::new(center.x + 5000, center.y - 4000);
+    let land =
+        |preset: WorldPreset, at| land_fraction(&preset.settings().build_elevation(7), at, 600);
+
+    let continent = land(WorldPreset::Continent, center);
+    assert!(continent > 0.5, "continent {continent}");
+    assert!(land(WorldPreset::Continent, far) < 0.05);
+
+    let archipelago = land(WorldPreset::Archipelago, center);
+    assert!(
+        archipelago > 0.05 && archipelago < continent,
+        "archipelago {archipelago}"
+    );
+    assert!(land(WorldPreset::InlandLakes, far) > 0.7);
+}
+
+#[test]
+fn settings_round_trip_through_ron() {
+    for preset in WorldPreset::ALL {
+        let settings = preset.settings();
+        let text = ron::to_string(&settings).unwrap();
+        assert_eq!(ron::from_str::<WorldGenSettings>(&text).unwrap(), settings);
+    }
+}
+
+#[test]
+fn presets_parse_from_their_names() {
+    assert_eq!("archipelago".parse(), Ok(WorldPreset::Archipelago));
+    assert_eq!("inland-lakes".parse(), Ok(WorldPreset::InlandLakes));
+    let error = "pangaea".parse::<WorldPreset>().unwrap_err();
+    assert!(error.contains("classic, continent, archipelago, inland-lakes"));
+    for preset in WorldPreset::ALL {
+        assert_eq!(preset.name().parse(), Ok(preset));
+    }
+}
+
+#[test]
+fn bounded_world_is_ocean_past_its_edge() {
+    let settings = WorldGenSettings {
+        bounds: WorldBounds::Grid,
+        ..WorldPreset::Continent.settings()
+    };
+    let elevation = settings.build_elevation(3);
+    let (cols, rows) = (GRID_COLS as f64, GRID_ROWS as f64);
+
+    for point in [
+        [0.0, rows / 2.0],
+        [cols - 1.0, 10.0],
+        [rows / 3.0, rows - 1.0],
+    ] {
+        assert!(elevation.get(point) < 0.0, "land at {point:?}");
+    }
+    for point in [[-50.0, -50.0], [cols + 300.0, 0.0], [cols / 2.0, -1.0]] {
+        assert_eq!(elevation.get(point), -1.0, "land at {point:?}");
+    }
+    // The middle of the continent is untouched
+    let infinite = WorldPreset::Continent.settings().build_elevation(3);
+    let middle = [cols / 2.0, rows / 2.0];
+    assert_eq!(elevation.get(middle), infinite.get(middle));
+}
+
+#[test]
+fn bounded_world_holds_the_grid() {
+    let bounds = WorldBounds::Grid;
+    let rect = bounds.world_rect().unwrap();
+
+    assert!(rect.contains(Vec2::ZERO));
+    let corner = GridPos::new(GRID_COLS as i32 - 1, GRID_ROWS as i32 - 1);
+    assert!(rect.contains(Vec2::from(corner.to_world())));
+    assert_eq!(WorldPos::from(rect.min).to_grid().x, 0);
+    assert!(bounds.contains(corner));
+    assert!(!bounds.contains(corner.offset(1, 0)));
+    assert!(bounds.contains_chunk(ChunkPos::new(0, 0)));
+    assert!(!bounds.contains_chunk(ChunkPos::new(-1, 0)));
+    assert!(!bounds.contains_chunk(corner.offset(1, 1).to_chunk()));
+    assert_eq!(WorldBounds::Infinite.world_rect(), None);
+}